@@ -1,4 +1,5 @@
 #![feature(question_mark)]
+extern crate chrono;
 extern crate clap;
 extern crate toml;
 extern crate exegesis;
@@ -12,7 +13,7 @@ use std::io::{Read, Write};
 use std::fmt::Write as FmtWrite;
 use std::path::Path;
 use std::process;
-use exegesis::{Website, HtmlFormatter, Rules};
+use exegesis::{Website, AnyFormatter, Error, Formatter, Rules, FORMATTER_NAMES};
 
 macro_rules! error(
     ($($arg:tt)*) => { {
@@ -37,7 +38,12 @@ fn download(url: &str) -> Result<Website, hyper::Error> {
 
     let response = request.send()?;
 
-    Ok(Website::from_response(url.to_string(), response))
+    let mut website = Website::from_response(url.to_string(), response);
+    // Responses without a Date header are taken as downloaded just now
+    if website.reference_time().is_none() {
+        website.set_reference_time(chrono::UTC::now().with_timezone(&chrono::FixedOffset::east(0)));
+    }
+    Ok(website)
 }
 
 fn main() {
@@ -52,16 +58,65 @@ fn main() {
             .help("Rule files")
             .multiple(true)
             .number_of_values(1)
-            .required(true)
+            .takes_value(true))
+        .arg(Arg::with_name("auto")
+            .short("a")
+            .long("auto")
+            .help("Guess the content of websites no rule matches"))
+        .arg(Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Output format")
+            .possible_values(FORMATTER_NAMES)
+            .default_value("html")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Write the output to FILE instead of the standard output")
+            .takes_value(true))
+        .arg(Arg::with_name("warnings")
+            .short("w")
+            .long("warnings")
+            .help("Print the issues encountered while extracting"))
+        .arg(Arg::with_name("fail-on-warnings")
+            .long("fail-on-warnings")
+            .help("Exit with a non-zero status if an issue was encountered while extracting"))
+        .arg(Arg::with_name("input")
+            .short("i")
+            .long("input")
+            .value_name("FILE")
+            .help("Extract from a local HTML file ('-' for the standard input) instead of \
+                   downloading the URL")
+            .requires("url")
+            .takes_value(true))
+        .arg(Arg::with_name("url")
+            .short("u")
+            .long("url")
+            .value_name("URL")
+            .help("Effective URL of the input, used to match rules")
+            .conflicts_with("URL")
+            .requires("input")
+            .takes_value(true))
+        .arg(Arg::with_name("reference-time")
+            .long("reference-time")
+            .value_name("TIME")
+            .help("Date and time relative dates are resolved against, in RFC 3339 format (defaults \
+                   to the Date header of the response)")
             .takes_value(true))
         .arg(Arg::with_name("URL")
-            .help("Sets the input file to use")
-            .required(true))
+            .help("URL of the Web page to extract"))
         .get_matches();
 
     let mut rules = Rules::default();
 
-    for value in matches.values_of_os("rules").unwrap() {
+    if !matches.is_present("rules") && !matches.is_present("auto") {
+        error!("At least one rule file is required, unless --auto is used");
+    }
+
+    for value in matches.values_of_os("rules").into_iter().flat_map(|values| values) {
         let source = match read_file(Path::new(value)) {
             Err(error) => error!("Error while reading '{}': {}", value.to_string_lossy(), error),
             Ok(s) => s,
@@ -94,18 +149,88 @@ fn main() {
         rules.append(new_rules);
     }
 
-    let url = matches.value_of("URL").unwrap();
-    let website = match download(url) {
-        Err(error) => error!("Error while loading '{}': {}", url, error),
-        Ok(w) => w,
+    let mut website = match matches.value_of_os("input") {
+        Some(input) => {
+            let url = matches.value_of("url").unwrap();
+            let result = if input == "-" {
+                Website::from_reader(url.to_string(), &mut io::stdin())
+            }
+            else {
+                Website::from_file(url.to_string(), input)
+            };
+            match result {
+                Err(error) => {
+                    error!("Error while reading '{}': {}", input.to_string_lossy(), error)
+                }
+                Ok(w) => w,
+            }
+        }
+        None => {
+            let url = match matches.value_of("URL") {
+                None => error!("A URL or an input file is required"),
+                Some(u) => u,
+            };
+            match download(url) {
+                Err(error) => error!("Error while loading '{}': {}", url, error),
+                Ok(w) => w,
+            }
+        }
+    };
+    if let Some(time) = matches.value_of("reference-time") {
+        match chrono::DateTime::parse_from_rfc3339(time) {
+            Err(error) => error!("Invalid reference time '{}': {}", time, error),
+            Ok(t) => website.set_reference_time(t),
+        }
+    }
+    let url = &website.request_url;
+
+    let result = match rules.extract_with_reports(&website) {
+        Err(Error::NoMatchingRule { .. }) if matches.is_present("auto") => {
+            exegesis::auto_extract(&website).map(|document| vec![document])
+        }
+        result => result,
     };
 
-    let docs = match rules.extract(&website) {
+    let (docs, reports): (Vec<_>, Vec<_>) = match result {
         Err(error) => error!("Error while extracting '{}': {}", url, error),
-        Ok(d) => d,
+        Ok(d) => d.into_iter().unzip(),
+    };
+
+    let has_warnings = reports.iter().any(|report| !report.is_empty());
+
+    if matches.is_present("warnings") {
+        for report in &reports {
+            for warning in &report.warnings {
+                writeln!(&mut io::stderr(), "Warning: {}", warning)
+                    .expect("Failed printing to stderr");
+            }
+        }
+    }
+
+    let format = matches.value_of("format").unwrap();
+    let formatter = match AnyFormatter::from_name(format) {
+        None => error!("Unknown format '{}'", format),
+        Some(f) => f,
     };
 
-    HtmlFormatter::default()
-        .write_full(&docs, &mut io::stdout())
-        .expect("Failed printing to stdout");
+    let mut output: Box<Write> = match matches.value_of_os("output") {
+        Some(path) => {
+            match fs::File::create(path) {
+                Err(error) => {
+                    error!("Error while creating '{}': {}", path.to_string_lossy(), error)
+                }
+                Ok(file) => Box::new(file),
+            }
+        }
+        None => Box::new(io::stdout()),
+    };
+
+    if let Err(error) = formatter.write_full(&docs, &mut output) {
+        error!("Error while writing the output: {}", error);
+    }
+    output.flush().expect("Failed to flush the output");
+
+    if has_warnings && matches.is_present("fail-on-warnings") {
+        process::exit(2);
+    }
 }
@@ -1,43 +1,722 @@
 use ::chrono;
 use ::regex;
-use std::error;
+use std::str::FromStr;
+use chrono::{Datelike, Duration, FixedOffset};
+use error::{ErrorContext, Error};
+use part::PartDate;
 
-pub fn parse_date(format: &str, input: &str) -> Result<chrono::NaiveDate, Box<error::Error>> {
-    let compile_re = regex::Regex::new("%.").unwrap();
+/// How the dates of a rule are parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DateFormat {
+    /// A `strftime`-like format (ex: `%Y-%m-%d`).
+    Pattern(DatePattern),
+    /// Recognizes common formats: ISO 8601, RFC 2822, `16 May 2016`, `May 16, 2016`, `05/16/2016`.
+    Auto,
+    /// Recognizes dates relative to the time of the download: `2 hours ago`, `yesterday`,
+    /// `last week`.
+    Relative,
+}
+
+/// Order of the day and the month in numeric dates like `05/06/2016`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DateOrder {
+    DayFirst,
+    MonthFirst,
+}
+
+impl Default for DateOrder {
+    fn default() -> DateOrder {
+        DateOrder::MonthFirst
+    }
+}
+
+/// Formats tried by `DateFormat::Auto`, before the numeric ones.
+const AUTO_FORMATS: &'static [&'static str] = &["%Y-%m-%dT%H:%M:%S%z",
+                                                "%Y-%m-%dT%H:%M%z",
+                                                "%Y-%m-%dT%H:%M:%S",
+                                                "%Y-%m-%dT%H:%M",
+                                                "%Y-%m-%d %H:%M:%S",
+                                                "%Y-%m-%d %H:%M",
+                                                "%Y-%m-%d",
+                                                "%a, %d %b %Y %H:%M:%S %z",
+                                                "%d %b %Y %H:%M:%S %z",
+                                                "%d %B %Y",
+                                                "%d. %B %Y",
+                                                "%B %d, %Y",
+                                                "%B %d %Y",
+                                                "%d.%m.%Y"];
+const DAY_FIRST_FORMATS: &'static [&'static str] = &["%d/%m/%Y", "%d-%m-%Y"];
+const MONTH_FIRST_FORMATS: &'static [&'static str] = &["%m/%d/%Y", "%m-%d-%Y"];
+
+lazy_static! {
+    static ref AUTO_PATTERNS: Vec<DatePattern> = compile_formats(AUTO_FORMATS);
+    static ref DAY_FIRST_PATTERNS: Vec<DatePattern> = compile_formats(DAY_FIRST_FORMATS);
+    static ref MONTH_FIRST_PATTERNS: Vec<DatePattern> = compile_formats(MONTH_FIRST_FORMATS);
+    static ref SPECIFIER_RE: regex::Regex = regex::Regex::new("%:?.").unwrap();
+    /// ISO 8601 UTC designators and fractional seconds, which `%z` and `%S` don't parse. The
+    /// seconds are part of the match so that the fraction and designator following them are.
+    static ref ISO_TIME_RE: regex::Regex =
+        regex::Regex::new(r"(\d{2}:\d{2}(?::\d{2})?)(?:\.\d+)?(Z\b)?").unwrap();
+    static ref RELATIVE_AGO_RE: regex::Regex = regex::Regex::new(RELATIVE_AGO).unwrap();
+    static ref RELATIVE_WORDS_RE: regex::Regex = regex::Regex::new(RELATIVE_WORDS).unwrap();
+    static ref RELATIVE_NOW_RE: regex::Regex = regex::Regex::new(RELATIVE_NOW).unwrap();
+}
+
+fn compile_formats(formats: &[&str]) -> Vec<DatePattern> {
+    formats.iter().map(|format| format.parse().unwrap()).collect()
+}
+
+/// Language of the month and weekday names of dates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DateLocale {
+    Dutch,
+    English,
+    French,
+    German,
+    Italian,
+    Portuguese,
+    Spanish,
+}
+
+impl Default for DateLocale {
+    fn default() -> DateLocale {
+        DateLocale::English
+    }
+}
+
+impl DateLocale {
+    /// Parses an ISO 639-1 language code (ex: `fr`).
+    pub fn from_str(s: &str) -> Option<DateLocale> {
+        match s {
+            "de" => Some(DateLocale::German),
+            "en" => Some(DateLocale::English),
+            "es" => Some(DateLocale::Spanish),
+            "fr" => Some(DateLocale::French),
+            "it" => Some(DateLocale::Italian),
+            "nl" => Some(DateLocale::Dutch),
+            "pt" => Some(DateLocale::Portuguese),
+            _ => None,
+        }
+    }
+
+    fn names(&self) -> Option<&'static LocaleNames> {
+        match *self {
+            DateLocale::Dutch => Some(&DUTCH),
+            DateLocale::English => None,
+            DateLocale::French => Some(&FRENCH),
+            DateLocale::German => Some(&GERMAN),
+            DateLocale::Italian => Some(&ITALIAN),
+            DateLocale::Portuguese => Some(&PORTUGUESE),
+            DateLocale::Spanish => Some(&SPANISH),
+        }
+    }
+}
+
+/// Lowercased month (January first) and weekday (Monday first) names of a language, including
+/// their usual abbreviations. Abbreviations which are ordinary words (German `so`, Portuguese
+/// `ter`) or could stand for a month as well as a weekday (`mar`) are left out, as are the
+/// two-letter weekday abbreviations.
+struct LocaleNames {
+    months: [&'static [&'static str]; 12],
+    weekdays: [&'static [&'static str]; 7],
+}
+
+impl LocaleNames {
+    /// English name of a month or weekday name.
+    fn english(&self, word: &str) -> Option<&'static str> {
+        let lowercase = word.to_lowercase();
+        self.months
+            .iter()
+            .zip(ENGLISH_MONTHS.iter())
+            .chain(self.weekdays.iter().zip(ENGLISH_WEEKDAYS.iter()))
+            .find(|&(localized, _)| localized.iter().any(|name| *name == lowercase))
+            .map(|(_, english)| *english)
+    }
+}
+
+const ENGLISH_MONTHS: [&'static str; 12] = ["January", "February", "March", "April", "May",
+                                            "June", "July", "August", "September", "October",
+                                            "November", "December"];
+const ENGLISH_WEEKDAYS: [&'static str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday",
+                                             "Friday", "Saturday", "Sunday"];
+
+static DUTCH: LocaleNames = LocaleNames {
+    months: [&["januari", "jan"],
+             &["februari", "feb"],
+             &["maart", "mrt"],
+             &["april", "apr"],
+             &["mei"],
+             &["juni", "jun"],
+             &["juli", "jul"],
+             &["augustus", "aug"],
+             &["september", "sep", "sept"],
+             &["oktober", "okt"],
+             &["november", "nov"],
+             &["december", "dec"]],
+    weekdays: [&["maandag"],
+               &["dinsdag"],
+               &["woensdag"],
+               &["donderdag"],
+               &["vrijdag"],
+               &["zaterdag"],
+               &["zondag"]],
+};
+
+static FRENCH: LocaleNames = LocaleNames {
+    months: [&["janvier", "janv"],
+             &["février", "fevrier", "févr", "fevr", "fév", "fev"],
+             &["mars"],
+             &["avril", "avr"],
+             &["mai"],
+             &["juin"],
+             &["juillet", "juil"],
+             &["août", "aout"],
+             &["septembre", "sept"],
+             &["octobre", "oct"],
+             &["novembre", "nov"],
+             &["décembre", "decembre", "déc", "dec"]],
+    weekdays: [&["lundi", "lun"],
+               &["mardi"],
+               &["mercredi", "mer"],
+               &["jeudi", "jeu"],
+               &["vendredi", "ven"],
+               &["samedi", "sam"],
+               &["dimanche", "dim"]],
+};
 
-    let pattern = compile_re.replace_all(&regex::quote(format), |captures: &regex::Captures| {
-        match captures.at(0) {
-                Some("%Y") | Some("%m") | Some("%d") => r"\d+",
-                Some("%b") | Some("%B") => r"\w+",
-                _ => r"\S+",
+static GERMAN: LocaleNames = LocaleNames {
+    months: [&["januar", "jänner", "jan", "jän"],
+             &["februar", "feb"],
+             &["märz", "maerz", "mär", "mrz"],
+             &["april", "apr"],
+             &["mai"],
+             &["juni", "jun"],
+             &["juli", "jul"],
+             &["august", "aug"],
+             &["september", "sep", "sept"],
+             &["oktober", "okt"],
+             &["november", "nov"],
+             &["dezember", "dez"]],
+    weekdays: [&["montag"],
+               &["dienstag"],
+               &["mittwoch"],
+               &["donnerstag"],
+               &["freitag"],
+               &["samstag", "sonnabend"],
+               &["sonntag"]],
+};
+
+static ITALIAN: LocaleNames = LocaleNames {
+    months: [&["gennaio", "gen"],
+             &["febbraio", "feb"],
+             &["marzo"],
+             &["aprile", "apr"],
+             &["maggio", "mag"],
+             &["giugno", "giu"],
+             &["luglio", "lug"],
+             &["agosto", "ago"],
+             &["settembre", "set"],
+             &["ottobre", "ott"],
+             &["novembre", "nov"],
+             &["dicembre", "dic"]],
+    weekdays: [&["lunedì", "lunedi", "lun"],
+               &["martedì", "martedi"],
+               &["mercoledì", "mercoledi", "mer"],
+               &["giovedì", "giovedi", "gio"],
+               &["venerdì", "venerdi", "ven"],
+               &["sabato", "sab"],
+               &["domenica", "dom"]],
+};
+
+static PORTUGUESE: LocaleNames = LocaleNames {
+    months: [&["janeiro", "jan"],
+             &["fevereiro", "fev"],
+             &["março", "marco"],
+             &["abril", "abr"],
+             &["maio", "mai"],
+             &["junho", "jun"],
+             &["julho", "jul"],
+             &["agosto", "ago"],
+             &["setembro", "set"],
+             &["outubro", "out"],
+             &["novembro", "nov"],
+             &["dezembro", "dez"]],
+    // Weekdays are usually written "segunda-feira", the first word being enough
+    weekdays: [&["segunda", "seg"],
+               &["terça", "terca"],
+               &["quarta", "qua"],
+               &["quinta", "qui"],
+               &["sexta", "sex"],
+               &["sábado", "sabado", "sáb", "sab"],
+               &["domingo", "dom"]],
+};
+
+static SPANISH: LocaleNames = LocaleNames {
+    months: [&["enero", "ene"],
+             &["febrero", "feb"],
+             &["marzo"],
+             &["abril", "abr"],
+             &["mayo", "may"],
+             &["junio", "jun"],
+             &["julio", "jul"],
+             &["agosto", "ago"],
+             &["septiembre", "setiembre", "sept", "sep", "set"],
+             &["octubre", "oct"],
+             &["noviembre", "nov"],
+             &["diciembre", "dic"]],
+    weekdays: [&["lunes", "lun"],
+               &["martes"],
+               &["miércoles", "miercoles", "mié", "mie"],
+               &["jueves", "jue"],
+               &["viernes", "vie"],
+               &["sábado", "sabado", "sáb", "sab"],
+               &["domingo", "dom"]],
+};
+
+/// Replaces the month and weekday names of `locale` found in `input` by their English
+/// equivalent, so dates can be parsed with `%B`, `%b`, `%A` and `%a`.
+pub fn translate_date(locale: DateLocale, input: &str) -> String {
+    let names = match locale.names() {
+        Some(names) => names,
+        None => return input.to_string(),
+    };
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find(&is_word_char) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = &rest[end..];
+        match names.english(word) {
+            Some(english) => {
+                result.push_str(english);
+                // The period ending abbreviations ("févr.") isn't part of the English names
+                if rest.starts_with('.') {
+                    rest = &rest[1..];
+                }
+            }
+            None => result.push_str(word),
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn has_offset(format: &str) -> bool {
+    ["%z", "%:z", "%#z"].iter().any(|specifier| format.contains(*specifier))
+}
+
+fn has_time(format: &str) -> bool {
+    ["%H", "%I", "%k", "%l", "%M", "%S", "%R", "%T"]
+        .iter()
+        .any(|specifier| format.contains(*specifier))
+}
+
+/// A `strftime`-like date format, compiled along with the regex finding its dates in texts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatePattern {
+    format: String,
+    regex: regex::Regex,
+}
+
+impl DatePattern {
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// Finds a date matching the format in `input`. The result holds a time of day if the format
+    /// has time specifiers, and a timezone offset if it has an offset specifier (`%z`).
+    pub fn parse(&self, input: &str) -> Result<PartDate, Error> {
+        let format = &self.format[..];
+        let date = self.regex
+            .captures(input)
+            .ok_or_else(|| {
+                Error::DateNotFound {
+                    format: format.to_string(),
+                    text: input.to_string(),
+                    context: ErrorContext::default(),
+                }
+            })?
+            .at(0)
+            .unwrap();
+
+        let parsed = if has_offset(format) {
+            chrono::DateTime::parse_from_str(date, format).map(PartDate::Offset)
+        }
+        else if has_time(format) {
+            chrono::NaiveDateTime::parse_from_str(date, format).map(PartDate::Local)
+        }
+        else {
+            chrono::NaiveDate::parse_from_str(date, format).map(PartDate::Date)
+        };
+        parsed.map_err(|cause| {
+            Error::InvalidDate {
+                format: format.to_string(),
+                text: date.to_string(),
+                cause: cause,
+                context: ErrorContext::default(),
+            }
+        })
+    }
+}
+
+impl FromStr for DatePattern {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<DatePattern, Error> {
+        let quoted = regex::quote(format);
+        let pattern = SPECIFIER_RE.replace_all(&quoted, |captures: &regex::Captures| {
+            match captures.at(0) {
+                    Some("%Y") | Some("%m") | Some("%d") | Some("%H") | Some("%M") | Some("%S") => {
+                        r"\d+"
+                    }
+                    Some("%b") | Some("%B") => r"\w+",
+                    Some("%z") | Some("%:z") => r"[+-]\d{2}:?\d{2}",
+                    _ => r"\S+",
+                }
+                .to_string()
+        });
+        let regex = regex::Regex::new(&pattern).map_err(|cause| {
+            Error::InvalidDateFormat {
+                format: format.to_string(),
+                cause: cause,
             }
-            .to_string()
+        })?;
+        Ok(DatePattern {
+            format: format.to_string(),
+            regex: regex,
+        })
+    }
+}
+
+/// Finds a date matching `format` in `input`, see `DatePattern::parse`. The format is compiled on
+/// each call.
+pub fn parse_date(format: &str, input: &str) -> Result<PartDate, Error> {
+    format.parse::<DatePattern>()?.parse(input)
+}
+
+/// Finds a date in `input` in one of the common formats listed by `DateFormat::Auto`.
+pub fn parse_auto_date(order: DateOrder, input: &str) -> Result<PartDate, Error> {
+    let input = ISO_TIME_RE.replace_all(input, |captures: &regex::Captures| {
+        let utc = if captures.at(2).is_some() { "+0000" } else { "" };
+        format!("{}{}", captures.at(1).unwrap(), utc)
     });
-    let re_pattern = regex::Regex::new(&pattern)?;
-    let date = re_pattern.captures(input).ok_or("No date found")?.at(0).unwrap();
-    Ok(chrono::NaiveDate::parse_from_str(date, format)?)
+
+    let numeric_patterns = match order {
+        DateOrder::DayFirst => &*DAY_FIRST_PATTERNS,
+        DateOrder::MonthFirst => &*MONTH_FIRST_PATTERNS,
+    };
+    let date = AUTO_PATTERNS.iter()
+        .chain(numeric_patterns.iter())
+        .filter_map(|pattern| pattern.parse(&input).ok())
+        .next()
+        .ok_or_else(|| {
+            Error::DateNotFound {
+                format: "auto".to_string(),
+                text: input.to_string(),
+                context: ErrorContext::default(),
+            }
+        });
+    date
+}
+
+/// Amounts of time units followed by `ago`. Units may be glued to numbers (`2h ago`) but not to
+/// words (`an hour ago`).
+const RELATIVE_AGO: &'static str = "(?i)\\b(?:(\\d+)\\s*\
+                                    (seconds?|secs?|minutes?|mins?|hours?|hrs?|h|days?|weeks?|\
+                                    months?|years?)|(?:an?|one)\\s+\
+                                    (seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?|\
+                                    months?|years?))\\s+ago\\b";
+/// Words meaning a day or a unit before the current time.
+const RELATIVE_WORDS: &'static str = "(?i)\\b(?:yesterday|last\\s+(week|month|year))\\b";
+/// Words meaning the current time, only recognized as the whole text since they are common
+/// outside of dates ("subscribe now").
+const RELATIVE_NOW: &'static str = "(?i)^\\s*(now|today)\\s*$";
+
+/// Largest amount of units accepted in relative dates.
+const MAX_RELATIVE_AMOUNT: i64 = 1000;
+
+/// The date `months` months before `date`, the day being clamped to the end of the month.
+fn months_before(date: chrono::NaiveDate, months: i64) -> Option<chrono::NaiveDate> {
+    let total = date.year() as i64 * 12 + date.month0() as i64 - months;
+    if total < 0 {
+        return None;
+    }
+    let (year, month) = ((total / 12) as i32, (total % 12) as u32 + 1);
+    (1..date.day() + 1)
+        .rev()
+        .filter_map(|day| chrono::NaiveDate::from_ymd_opt(year, month, day))
+        .next()
+}
+
+/// Finds a date relative to `now` in `input`, like `2 hours ago`, `yesterday` or `last week`.
+/// Amounts of seconds, minutes and hours give a time, larger ones only a day.
+pub fn parse_relative_date(now: &chrono::DateTime<FixedOffset>,
+                           input: &str)
+                           -> Result<PartDate, Error> {
+    let (amount, unit) = if let Some(captures) = RELATIVE_AGO_RE.captures(input) {
+        let amount = match captures.at(1) {
+            Some(digits) => {
+                digits.parse::<i64>().ok().and_then(|amount| {
+                    if amount <= MAX_RELATIVE_AMOUNT { Some(amount) } else { None }
+                })
+            }
+            None => Some(1),
+        };
+        (amount, captures.at(2).or(captures.at(3)).unwrap().to_lowercase())
+    }
+    else if let Some(captures) = RELATIVE_WORDS_RE.captures(input) {
+        match captures.at(1) {
+            Some(unit) => (Some(1), unit.to_lowercase()),
+            None => (Some(1), "day".to_string()),
+        }
+    }
+    else if let Some(captures) = RELATIVE_NOW_RE.captures(input) {
+        if captures.at(1).unwrap().to_lowercase() == "today" {
+            (Some(0), "day".to_string())
+        }
+        else {
+            (Some(0), "second".to_string())
+        }
+    }
+    else {
+        (None, String::new())
+    };
+
+    let today = now.naive_local().date();
+    let date = amount.and_then(|amount| {
+        let before = |duration: Duration| PartDate::Offset(now.clone() - duration);
+        match unit.trim_right_matches('s') {
+            "second" | "sec" => Some(before(Duration::seconds(amount))),
+            "minute" | "min" => Some(before(Duration::minutes(amount))),
+            "hour" | "hr" | "h" => Some(before(Duration::hours(amount))),
+            "day" => Some(PartDate::Date(today - Duration::days(amount))),
+            "week" => Some(PartDate::Date(today - Duration::weeks(amount))),
+            "month" => months_before(today, amount).map(PartDate::Date),
+            "year" => months_before(today, amount * 12).map(PartDate::Date),
+            _ => None,
+        }
+    });
+    date.ok_or_else(|| {
+        Error::DateNotFound {
+            format: "relative".to_string(),
+            text: input.to_string(),
+            context: ErrorContext::default(),
+        }
+    })
+}
+
+/// Finds a date in `input` with the first of `formats` matching it. If none does, the error is
+/// the one of the first format. `formats` must not be empty. Relative dates are resolved against
+/// `now`, and can't be parsed without it.
+pub fn parse_date_formats(formats: &[DateFormat],
+                          order: DateOrder,
+                          now: Option<&chrono::DateTime<FixedOffset>>,
+                          input: &str)
+                          -> Result<PartDate, Error> {
+    let mut first_error = None;
+    for format in formats {
+        let result = match *format {
+            DateFormat::Pattern(ref pattern) => pattern.parse(input),
+            DateFormat::Auto => parse_auto_date(order, input),
+            DateFormat::Relative => {
+                match now {
+                    Some(now) => parse_relative_date(now, input),
+                    None => {
+                        Err(Error::NoReferenceTime {
+                            text: input.to_string(),
+                            context: ErrorContext::default(),
+                        })
+                    }
+                }
+            }
+        };
+        match result {
+            Ok(date) => return Ok(date),
+            Err(error) => {
+                if first_error.is_none() {
+                    first_error = Some(error);
+                }
+            }
+        }
+    }
+    Err(first_error.expect("No date format"))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_date;
-    use ::chrono::NaiveDate;
+    use super::{DateFormat, DateLocale, DateOrder, parse_auto_date, parse_date,
+                parse_date_formats, parse_relative_date, translate_date};
+    use ::chrono::{Duration, FixedOffset, NaiveDate, TimeZone};
+    use ::error::Error;
+    use ::part::PartDate;
 
     #[test]
     fn test_parse_date() {
         let date = parse_date("%Y-%m-%d", "2015-10-10").unwrap();
-        assert_eq!(date, NaiveDate::from_ymd(2015, 10, 10));
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2015, 10, 10)));
     }
 
     #[test]
     fn test_parse_date_locale() {
         let date = parse_date("%Y %b %d", "2015 Jul 10").unwrap();
-        assert_eq!(date, NaiveDate::from_ymd(2015, 7, 10));
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2015, 7, 10)));
     }
 
     #[test]
     fn test_parse_date_with_surrounding_content() {
         let date = parse_date("%Y-%m-%d", "blah 2015-10-10").unwrap();
-        assert_eq!(date, NaiveDate::from_ymd(2015, 10, 10));
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2015, 10, 10)));
+    }
+
+    #[test]
+    fn test_parse_date_with_time() {
+        let date = parse_date("%Y-%m-%d %H:%M", "on 2015-10-10 08:30, by").unwrap();
+        assert_eq!(date,
+                   PartDate::Local(NaiveDate::from_ymd(2015, 10, 10).and_hms(8, 30, 0)));
+    }
+
+    #[test]
+    fn test_parse_date_with_offset() {
+        let date = parse_date("%Y-%m-%dT%H:%M%z", "on 2015-10-10T08:30+0200").unwrap();
+        assert_eq!(date,
+                   PartDate::Offset(FixedOffset::east(2 * 3600)
+                       .ymd(2015, 10, 10)
+                       .and_hms(8, 30, 0)));
+    }
+
+    #[test]
+    fn test_parse_relative_date() {
+        let now = FixedOffset::east(2 * 3600).ymd(2016, 3, 31).and_hms(10, 30, 0);
+        let date = parse_relative_date(&now, "Posted 2 hours ago").unwrap();
+        assert_eq!(date,
+                   PartDate::Offset(FixedOffset::east(2 * 3600)
+                       .ymd(2016, 3, 31)
+                       .and_hms(8, 30, 0)));
+        let date = parse_relative_date(&now, "an hour ago").unwrap();
+        assert_eq!(date.readable(), "2016-03-31 09:30 +0200");
+
+        let date = |input| parse_relative_date(&now, input).unwrap();
+        assert_eq!(date("Yesterday"), PartDate::Date(NaiveDate::from_ymd(2016, 3, 30)));
+        assert_eq!(date("3 days ago"), PartDate::Date(NaiveDate::from_ymd(2016, 3, 28)));
+        assert_eq!(date("last week"), PartDate::Date(NaiveDate::from_ymd(2016, 3, 24)));
+        assert_eq!(date("a month ago"), PartDate::Date(NaiveDate::from_ymd(2016, 2, 29)));
+        assert_eq!(date("2 years ago"), PartDate::Date(NaiveDate::from_ymd(2014, 3, 31)));
+
+        assert_eq!(date("2h ago"), PartDate::Offset(now.clone() - Duration::hours(2)));
+        assert_eq!(date(" Today "), PartDate::Date(NaiveDate::from_ymd(2016, 3, 31)));
+        assert_eq!(date("now"), PartDate::Offset(now.clone()));
+
+        for input in &["2016-03-31", "Ah ago", "a h ago", "Subscribe now", "today's news"] {
+            match parse_relative_date(&now, input) {
+                Err(Error::DateNotFound { ref format, .. }) => assert_eq!(format, "relative"),
+                other => panic!("Unexpected result {:?} for {:?}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        let now = FixedOffset::east(0).ymd(2016, 5, 17).and_hms(0, 0, 0);
+        let formats = vec![DateFormat::Pattern("%B %d, %Y".parse().unwrap()),
+                           DateFormat::Pattern("%Y-%m-%d".parse().unwrap()),
+                           DateFormat::Relative];
+        let date = parse_date_formats(&formats, DateOrder::MonthFirst, Some(&now), "2016-05-16")
+            .unwrap();
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2016, 5, 16)));
+        let date = parse_date_formats(&formats, DateOrder::MonthFirst, Some(&now), "yesterday")
+            .unwrap();
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2016, 5, 16)));
+
+        match parse_date_formats(&formats, DateOrder::MonthFirst, Some(&now), "blah") {
+            Err(Error::DateNotFound { ref format, .. }) => assert_eq!(format, "%B %d, %Y"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let relative = [DateFormat::Relative];
+        match parse_date_formats(&relative, DateOrder::MonthFirst, None, "yesterday") {
+            Err(Error::NoReferenceTime { ref text, .. }) => assert_eq!(text, "yesterday"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_auto_date() {
+        let may_16 = PartDate::Date(NaiveDate::from_ymd(2016, 5, 16));
+        for input in &["Posted on 2016-05-16", "16 May 2016", "May 16, 2016", "05/16/2016"] {
+            assert_eq!(parse_auto_date(DateOrder::MonthFirst, input).unwrap(), may_16);
+        }
+        assert_eq!(parse_auto_date(DateOrder::DayFirst, "16/05/2016").unwrap(), may_16);
+        assert_eq!(parse_auto_date(DateOrder::DayFirst, "05/06/2016").unwrap(),
+                   PartDate::Date(NaiveDate::from_ymd(2016, 6, 5)));
+
+        let offset = FixedOffset::east(2 * 3600).ymd(2016, 5, 16).and_hms(10, 30, 0);
+        assert_eq!(parse_auto_date(DateOrder::MonthFirst, "2016-05-16T10:30:00.123+02:00")
+                       .unwrap(),
+                   PartDate::Offset(offset));
+        assert_eq!(parse_auto_date(DateOrder::MonthFirst, "Mon, 16 May 2016 10:30:00 +0200")
+                       .unwrap(),
+                   PartDate::Offset(offset));
+        let utc = FixedOffset::east(0).ymd(2016, 5, 16).and_hms(8, 30, 0);
+        assert_eq!(parse_auto_date(DateOrder::MonthFirst, "2016-05-16T08:30:00Z").unwrap(),
+                   PartDate::Offset(utc));
+        assert_eq!(parse_auto_date(DateOrder::MonthFirst, "2016-05-16T08:30:00.5Z").unwrap(),
+                   PartDate::Offset(utc));
+        assert_eq!(parse_auto_date(DateOrder::MonthFirst, "2016-05-16T08:30Z").unwrap(),
+                   PartDate::Offset(utc));
+    }
+
+    #[test]
+    fn test_translate_date() {
+        assert_eq!(translate_date(DateLocale::French, "lundi 16 mai 2016"),
+                   "Monday 16 May 2016");
+        assert_eq!(translate_date(DateLocale::German, "16. MÄRZ 2016"), "16. March 2016");
+        assert_eq!(translate_date(DateLocale::English, "16 mai 2016"), "16 mai 2016");
+
+        // "mar" could be March as well as Tuesday
+        assert_eq!(translate_date(DateLocale::Italian, "mar 15 marzo 2016"),
+                   "mar 15 March 2016");
+        assert_eq!(translate_date(DateLocale::Spanish, "mar, 15 de marzo de 2016"),
+                   "mar, 15 de March de 2016");
+        // Two-letter weekday abbreviations are ordinary words
+        assert_eq!(translate_date(DateLocale::German, "so am 16. Mai 2016, do es"),
+                   "so am 16. May 2016, do es");
+
+        assert_eq!(translate_date(DateLocale::French, "3 janv. 2016"), "3 January 2016");
+
+        let date = parse_auto_date(DateOrder::DayFirst,
+                                   &translate_date(DateLocale::German, "16. Mai 2016"))
+            .unwrap();
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2016, 5, 16)));
+        let date = parse_auto_date(DateOrder::DayFirst,
+                                   &translate_date(DateLocale::French, "16 févr. 2016"))
+            .unwrap();
+        assert_eq!(date, PartDate::Date(NaiveDate::from_ymd(2016, 2, 16)));
+    }
+
+    #[test]
+    fn test_invalid_date_format() {
+        match parse_date("%d %(", "16 (") {
+            Err(Error::InvalidDateFormat { ref format, .. }) => assert_eq!(format, "%d %("),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_date_not_found() {
+        match parse_date("%Y-%m-%d", "blah") {
+            Err(Error::DateNotFound { ref text, .. }) => assert_eq!(text, "blah"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_date_invalid() {
+        match parse_date("%Y-%m-%d", "2015-13-10") {
+            Err(Error::InvalidDate { ref text, .. }) => assert_eq!(text, "2015-13-10"),
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 }
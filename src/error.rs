@@ -0,0 +1,238 @@
+use ::chrono;
+use ::kuchiki;
+use ::regex;
+use std::error;
+use std::fmt;
+use extractor::SelectorKind;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The website content is not an HTML document.
+    NoDom { url: String },
+    /// No rule matches the website URL.
+    NoMatchingRule { url: String },
+    /// The automatic extraction didn't find any content.
+    NoContent { url: String },
+    /// An element matched by a selector lacks an attribute needed to build its part.
+    MissingAttribute {
+        kind: SelectorKind,
+        element: String,
+        attribute: String,
+        context: ErrorContext,
+    },
+    /// An embedded media links to a URL which isn't `http` or `https`.
+    UnsafeUrl {
+        kind: SelectorKind,
+        element: String,
+        url: String,
+        context: ErrorContext,
+    },
+    /// The size of an image can't be parsed.
+    InvalidDimension {
+        kind: SelectorKind,
+        element: String,
+        property: &'static str,
+        value: String,
+        context: ErrorContext,
+    },
+    /// A date format can't be turned into a pattern finding dates in texts.
+    InvalidDateFormat {
+        format: String,
+        cause: regex::Error,
+    },
+    /// A date selector matched an element, but the rule has no date format.
+    NoDateFormat {
+        kind: SelectorKind,
+        element: String,
+        context: ErrorContext,
+    },
+    /// No text matching the date format has been found.
+    DateNotFound {
+        format: String,
+        text: String,
+        context: ErrorContext,
+    },
+    /// A relative date can't be resolved without knowing when the page was downloaded.
+    NoReferenceTime {
+        text: String,
+        context: ErrorContext,
+    },
+    /// A text matching the date format has been found but is not a valid date.
+    InvalidDate {
+        format: String,
+        text: String,
+        cause: chrono::ParseError,
+        context: ErrorContext,
+    },
+}
+
+/// Where an extraction error happened, filled in once the rule and the page are known.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Name of the rule used to extract the page, `None` for the automatic extraction.
+    pub rule: Option<String>,
+    /// URL of the page.
+    pub url: Option<String>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref rule) = self.rule {
+            write!(formatter, " (rule '{}')", rule)?;
+        }
+        if let Some(ref url) = self.url {
+            write!(formatter, " in {}", url)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// Short CSS-like description of an element, used as error context (ex: `img#logo.big`).
+    pub fn describe_element(element: &kuchiki::ElementData) -> String {
+        let attributes = element.attributes.borrow();
+        let mut result = element.name.local.to_string();
+        if let Some(id) = attributes.get("id") {
+            result.push('#');
+            result.push_str(id);
+        }
+        if let Some(class) = attributes.get("class") {
+            for class_name in class.split_whitespace() {
+                result.push('.');
+                result.push_str(class_name);
+            }
+        }
+        result
+    }
+
+    /// ErrorContext of an extraction error, `None` for other errors.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match *self {
+            Error::MissingAttribute { ref context, .. } |
+            Error::UnsafeUrl { ref context, .. } |
+            Error::InvalidDimension { ref context, .. } |
+            Error::NoDateFormat { ref context, .. } |
+            Error::DateNotFound { ref context, .. } |
+            Error::NoReferenceTime { ref context, .. } |
+            Error::InvalidDate { ref context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Records the rule and the page an extraction error happened in.
+    pub fn set_context(&mut self, rule: Option<&str>, url: &str) {
+        match *self {
+            Error::MissingAttribute { ref mut context, .. } |
+            Error::UnsafeUrl { ref mut context, .. } |
+            Error::InvalidDimension { ref mut context, .. } |
+            Error::NoDateFormat { ref mut context, .. } |
+            Error::DateNotFound { ref mut context, .. } |
+            Error::NoReferenceTime { ref mut context, .. } |
+            Error::InvalidDate { ref mut context, .. } => {
+                context.rule = rule.map(String::from);
+                context.url = Some(url.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NoDom { ref url } => write!(formatter, "The website {} has no DOM", url),
+            Error::NoMatchingRule { ref url } => {
+                write!(formatter, "No rule matching the website {}", url)
+            }
+            Error::NoContent { ref url } => {
+                write!(formatter, "No content found in the website {}", url)
+            }
+            Error::MissingAttribute { ref kind, ref element, ref attribute, ref context } => {
+                write!(formatter,
+                       "The {} element {} has no {} attribute{}",
+                       kind,
+                       element,
+                       attribute,
+                       context)
+            }
+            Error::UnsafeUrl { ref kind, ref element, ref url, ref context } => {
+                write!(formatter,
+                       "The {} element {} links to the unsafe URL {:?}{}",
+                       kind,
+                       element,
+                       url,
+                       context)
+            }
+            Error::InvalidDimension { ref kind,
+                                      ref element,
+                                      ref property,
+                                      ref value,
+                                      ref context } => {
+                write!(formatter,
+                       "The {} element {} has an invalid {}: {:?}{}",
+                       kind,
+                       element,
+                       property,
+                       value,
+                       context)
+            }
+            Error::InvalidDateFormat { ref format, ref cause } => {
+                write!(formatter, "Invalid date format '{}': {}", format, cause)
+            }
+            Error::NoDateFormat { ref kind, ref element, ref context } => {
+                write!(formatter,
+                       "The {} element {} can't be parsed: no date format{}",
+                       kind,
+                       element,
+                       context)
+            }
+            Error::DateNotFound { ref format, ref text, ref context } => {
+                write!(formatter,
+                       "No date matching '{}' found in {:?}{}",
+                       format,
+                       text,
+                       context)
+            }
+            Error::NoReferenceTime { ref text, ref context } => {
+                write!(formatter,
+                       "No reference time to resolve the relative date {:?}{}",
+                       text,
+                       context)
+            }
+            Error::InvalidDate { ref format, ref text, ref cause, ref context } => {
+                write!(formatter,
+                       "Invalid date {:?} for format '{}': {}{}",
+                       text,
+                       format,
+                       cause,
+                       context)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::NoDom { .. } => "website has no DOM",
+            Error::NoMatchingRule { .. } => "no rule matching the website",
+            Error::NoContent { .. } => "no content found",
+            Error::MissingAttribute { .. } => "missing attribute",
+            Error::UnsafeUrl { .. } => "unsafe URL",
+            Error::InvalidDimension { .. } => "invalid dimension",
+            Error::InvalidDateFormat { .. } => "invalid date format",
+            Error::NoDateFormat { .. } => "no date format",
+            Error::DateNotFound { .. } => "date not found",
+            Error::NoReferenceTime { .. } => "no reference time",
+            Error::InvalidDate { .. } => "invalid date",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::InvalidDateFormat { ref cause, .. } => Some(cause),
+            Error::InvalidDate { ref cause, .. } => Some(cause),
+            _ => None,
+        }
+    }
+}
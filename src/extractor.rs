@@ -1,42 +1,73 @@
-use ::kuchiki;
 use ::chrono;
+use ::kuchiki;
+use ::url::Url;
+use std::cmp;
 use std::mem;
-use std::error;
 use std::fmt;
 use std::str;
-use date::parse_date;
-use part::{Part, Document};
+use std::u32;
+use date::{DateFormat, DateLocale, DateOrder, parse_auto_date, parse_date_formats, translate_date};
+use error::{Error, ErrorContext};
+use metadata;
+use part::{PartDate, Dimension, EmbedKind, Part, Document, TableCell, is_safe_url};
+use report::{Report, Warning, dom_path};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum SelectorKind {
+    Attribution,
+    Author,
+    Blockquote,
+    CanonicalUrl,
+    CodeBlock,
     Date,
     Emphasis,
+    Figure,
     Header1,
     Header2,
     Header3,
     Image,
+    InlineCode,
+    Language,
     Link,
     List,
     ListItem,
+    Media,
+    OrderedList,
     Paragraph,
     PublicationDate,
+    Summary,
+    Table,
+    Tag,
     Title,
 }
 
 impl SelectorKind {
     pub fn from_str(s: &str) -> Option<SelectorKind> {
         match s {
+            "attribution" => Some(SelectorKind::Attribution),
+            "author" => Some(SelectorKind::Author),
+            "blockquote" => Some(SelectorKind::Blockquote),
+            "canonical-url" => Some(SelectorKind::CanonicalUrl),
+            "code-block" => Some(SelectorKind::CodeBlock),
             "date" => Some(SelectorKind::Date),
             "emphasis" => Some(SelectorKind::Emphasis),
+            "figure" => Some(SelectorKind::Figure),
             "header1" => Some(SelectorKind::Header1),
             "header2" => Some(SelectorKind::Header2),
             "header3" => Some(SelectorKind::Header3),
             "image" => Some(SelectorKind::Image),
+            "inline-code" => Some(SelectorKind::InlineCode),
+            "language" => Some(SelectorKind::Language),
             "link" => Some(SelectorKind::Link),
             "list" => Some(SelectorKind::List),
             "list-item" => Some(SelectorKind::ListItem),
+            "media" => Some(SelectorKind::Media),
+            "ordered-list" => Some(SelectorKind::OrderedList),
             "paragraph" => Some(SelectorKind::Paragraph),
             "publication-date" => Some(SelectorKind::PublicationDate),
+            "summary" => Some(SelectorKind::Summary),
+            "table" => Some(SelectorKind::Table),
+            "tag" => Some(SelectorKind::Tag),
             "title" => Some(SelectorKind::Title),
             _ => None,
         }
@@ -46,17 +77,30 @@ impl SelectorKind {
 impl fmt::Display for SelectorKind {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter.write_str(match *self {
+            SelectorKind::Attribution => "attribution",
+            SelectorKind::Author => "author",
+            SelectorKind::Blockquote => "blockquote",
+            SelectorKind::CanonicalUrl => "canonical-url",
+            SelectorKind::CodeBlock => "code-block",
             SelectorKind::Date => "date",
             SelectorKind::Emphasis => "emphasis",
+            SelectorKind::Figure => "figure",
             SelectorKind::Header1 => "header1",
             SelectorKind::Header2 => "header2",
             SelectorKind::Header3 => "header3",
             SelectorKind::Image => "image",
+            SelectorKind::InlineCode => "inline-code",
+            SelectorKind::Language => "language",
             SelectorKind::Link => "link",
             SelectorKind::List => "list",
             SelectorKind::ListItem => "list-item",
+            SelectorKind::Media => "media",
+            SelectorKind::OrderedList => "ordered-list",
             SelectorKind::Paragraph => "paragraph",
             SelectorKind::PublicationDate => "publication-date",
+            SelectorKind::Summary => "summary",
+            SelectorKind::Table => "table",
+            SelectorKind::Tag => "tag",
             SelectorKind::Title => "title",
         })
     }
@@ -84,6 +128,8 @@ macro_rules! sdv_from_option{
 pub struct Selector {
     kind: SelectorKind,
     query: kuchiki::Selectors, // absolute: bool,
+    source: Option<String>,
+    attribute: Option<String>,
     priority: u16,
 }
 
@@ -92,6 +138,8 @@ impl fmt::Debug for Selector {
         formatter.debug_struct("Selector")
             .field("kind", &self.kind)
             .field("query", &SimpleDebugValue("..."))
+            .field("source", &self.source)
+            .field("attribute", &self.attribute)
             .field("priority", &self.priority)
             .finish()
     }
@@ -102,38 +150,103 @@ impl Selector {
         Selector {
             kind: kind,
             query: query,
+            source: None,
+            attribute: None,
             priority: 0,
         }
     }
 
+    /// Sets the source of the CSS selector, used to report extraction issues.
+    pub fn source(mut self, source: String) -> Selector {
+        self.source = Some(source);
+        self
+    }
+
+    /// Reads the value of the part from an attribute of the matched elements instead of their
+    /// content. For images, links and media, the attribute holds the URL.
+    pub fn attribute(mut self, attribute: String) -> Selector {
+        self.attribute = Some(attribute);
+        self
+    }
+
     pub fn priority(mut self, p: u16) -> Selector {
         self.priority = p;
         self
     }
 }
 
-#[derive(Default)]
 pub struct ExtractorOptions {
-    pub on_parse_error: Option<Box<Fn(Box<error::Error>)>>,
-    pub date_format: Option<String>,
+    /// Formats tried in order to parse dates.
+    pub date_formats: Vec<DateFormat>,
+    /// Order of the day and month in numeric dates parsed by `DateFormat::Auto`.
+    pub date_order: DateOrder,
+    /// Language of the month and weekday names in the text of dates.
+    pub date_locale: DateLocale,
+    /// Attributes holding a machine-readable date, read before the text of date elements.
+    pub date_attributes: Vec<String>,
     pub root_selector: Option<kuchiki::Selectors>,
+    /// Elements matching one of these selectors are skipped along with their descendants.
+    pub exclude_selectors: Vec<kuchiki::Selectors>,
+    /// Keep link and image URLs as found in the DOM instead of resolving them against the page
+    /// URL.
+    pub keep_raw_urls: bool,
+    /// Complete the title, publication date and authors without selectors with the metadata
+    /// published by the page (OpenGraph, `<meta>` tags, JSON-LD).
+    pub metadata_fallback: bool,
+}
+
+impl Default for ExtractorOptions {
+    fn default() -> ExtractorOptions {
+        ExtractorOptions {
+            date_formats: Vec::new(),
+            date_order: DateOrder::default(),
+            date_locale: DateLocale::default(),
+            date_attributes: vec!["datetime".to_string(),
+                                  "content".to_string(),
+                                  "data-date".to_string()],
+            root_selector: None,
+            exclude_selectors: Vec::new(),
+            keep_raw_urls: false,
+            metadata_fallback: true,
+        }
+    }
 }
 
 impl fmt::Debug for ExtractorOptions {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter.debug_struct("ExtractorOptions")
-            .field("on_parse_error",
-                   &sdv_from_option!(self.on_parse_error, "fn"))
-            .field("date_format", &self.date_format)
+            .field("date_formats", &self.date_formats)
+            .field("date_order", &self.date_order)
+            .field("date_locale", &self.date_locale)
+            .field("date_attributes", &self.date_attributes)
             .field("root_selector",
                    &sdv_from_option!(self.root_selector, "selector"))
+            .field("exclude_selectors",
+                   &SimpleDebugValue(if self.exclude_selectors.is_empty() {
+                       "[]"
+                   }
+                   else {
+                       "[...]"
+                   }))
+            .field("keep_raw_urls", &self.keep_raw_urls)
+            .field("metadata_fallback", &self.metadata_fallback)
             .finish()
     }
 }
 
 struct ExtractorResult<'a> {
     document: &'a mut Document,
+    report: &'a mut Report,
+    base_url: Option<&'a Url>,
+    /// Reference time of relative dates.
+    now: Option<&'a chrono::DateTime<chrono::FixedOffset>>,
     parent_children: Vec<Part>,
+    /// Whether the current node is inside a blockquote.
+    in_blockquote: bool,
+    /// Attribution of the current blockquote.
+    attribution: Option<Vec<Part>>,
+    /// Non-fatal errors raised while handling the current part.
+    errors: Vec<Error>,
 }
 
 #[derive(Default)]
@@ -151,6 +264,298 @@ impl fmt::Debug for Extractor {
     }
 }
 
+/// Verbatim text of the parts, including the text of nested parts.
+fn full_text(content: &[Part]) -> String {
+    content.iter().map(|part| part.text()).collect()
+}
+
+/// Language of a code element, read from a `language-xxx` or `lang-xxx` class on the element or
+/// its first `code` descendant.
+fn code_language(node: &kuchiki::NodeDataRef<kuchiki::ElementData>) -> Option<String> {
+    fn from_class(element: &kuchiki::ElementData) -> Option<String> {
+        let attributes = element.attributes.borrow();
+        let class = match attributes.get("class") {
+            Some(class) => class,
+            None => return None,
+        };
+        let language = class.split_whitespace()
+            .filter_map(|name| {
+                if name.starts_with("language-") {
+                    Some(&name["language-".len()..])
+                }
+                else if name.starts_with("lang-") {
+                    Some(&name["lang-".len()..])
+                }
+                else {
+                    None
+                }
+            })
+            .find(|language| !language.is_empty())
+            .map(|language| language.to_string());
+        language
+    }
+
+    from_class(node).or_else(|| {
+        node.as_node()
+            .select("code")
+            .ok()
+            .and_then(|mut codes| codes.next())
+            .and_then(|code| from_class(&code))
+    })
+}
+
+pub fn is_element(node: &kuchiki::NodeRef, name: &str) -> bool {
+    node.as_element().map_or(false, |el| el.name.local.eq_str_ignore_ascii_case(name))
+}
+
+/// Whether the node is an element with one of the given names.
+pub fn is_any_element(node: &kuchiki::NodeRef, names: &[&str]) -> bool {
+    names.iter().any(|name| is_element(node, name))
+}
+
+/// Parses the descriptors of a `srcset` candidate as `(is_width, value)`.
+fn srcset_descriptor(descriptors: &str) -> Option<(bool, f64)> {
+    let descriptor = descriptors.split_whitespace().next().unwrap_or("1x");
+    let (is_width, value) = if descriptor.ends_with('w') {
+        (true, &descriptor[..descriptor.len() - 1])
+    }
+    else if descriptor.ends_with('x') {
+        (false, &descriptor[..descriptor.len() - 1])
+    }
+    else {
+        return None;
+    };
+    value.parse::<f64>().ok().map(|value| (is_width, value))
+}
+
+/// Candidates of a `srcset` attribute, as `(url, is_width, value)` where `value` is the width
+/// (`640w`) or the pixel density (`2x`) of the candidate. Follows the HTML tokenizer: URLs may
+/// contain commas, candidates being separated by a comma after whitespace or descriptors.
+fn srcset_candidates(srcset: &str) -> Vec<(String, bool, f64)> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_left_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url = &rest[..url_end];
+        rest = &rest[url_end..];
+
+        // A URL ending with a comma has no descriptors
+        let descriptors = if url.ends_with(',') {
+            ""
+        }
+        else {
+            let mut depth = 0;
+            let mut end = rest.len();
+            for (index, c) in rest.char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth > 0 => depth -= 1,
+                    ',' if depth == 0 => {
+                        end = index;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let descriptors = &rest[..end];
+            rest = &rest[end..];
+            descriptors
+        };
+
+        let url = url.trim_right_matches(',');
+        if let Some((is_width, value)) = srcset_descriptor(descriptors) {
+            if !url.is_empty() {
+                candidates.push((url.to_string(), is_width, value));
+            }
+        }
+    }
+    candidates
+}
+
+/// Source of an image, picking the largest candidate from `srcset` attributes (including
+/// `<source>` elements of a parent `<picture>`) and falling back to lazy-loading attributes and
+/// `src`.
+fn image_source(element: &kuchiki::NodeDataRef<kuchiki::ElementData>) -> Option<String> {
+    let mut srcsets = Vec::new();
+    {
+        let attributes = element.attributes.borrow();
+        for name in &["srcset", "data-srcset"] {
+            if let Some(srcset) = attributes.get(*name) {
+                srcsets.push(srcset.to_string());
+            }
+        }
+    }
+
+    if let Some(parent) = element.as_node().parent() {
+        if is_element(&parent, "picture") {
+            for source in parent.children().filter(|node| is_element(node, "source")) {
+                let source_element = source.as_element().unwrap();
+                let attributes = source_element.attributes.borrow();
+                if attributes.get("media").is_none() {
+                    if let Some(srcset) = attributes.get("srcset")
+                        .or_else(|| attributes.get("data-srcset")) {
+                        srcsets.push(srcset.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut best: Option<(String, bool, f64)> = None;
+    for candidate in srcsets.iter().flat_map(|srcset| srcset_candidates(srcset)) {
+        let is_better = match best {
+            Some((_, is_width, value)) => (candidate.1, candidate.2) > (is_width, value),
+            None => true,
+        };
+        if is_better {
+            best = Some(candidate);
+        }
+    }
+
+    if let Some((url, _, _)) = best {
+        return Some(url);
+    }
+
+    let attributes = element.attributes.borrow();
+    let source = ["data-src", "data-original", "data-lazy-src", "src"]
+        .iter()
+        .filter_map(|name| attributes.get(*name))
+        .find(|value| !value.trim().is_empty())
+        .map(|value| value.trim().to_string());
+    source
+}
+
+/// Size of an image along one axis.
+enum Size {
+    Pixels(u32),
+    Other(Dimension),
+}
+
+/// Parses an HTML dimension attribute or a CSS length (ex: `640`, `640px`, `50%`, `20em`).
+fn parse_size(value: &str) -> Option<Size> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_digit(10) && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = unit.trim().to_lowercase();
+    let pixels = match number.parse::<f64>() {
+        Ok(pixels) => pixels,
+        Err(_) => return None,
+    };
+    if unit.is_empty() || unit == "px" {
+        // Out of range values are invalid rather than saturated
+        let pixels = pixels.round();
+        if pixels.is_finite() && pixels >= 0. && pixels <= u32::MAX as f64 {
+            Some(Size::Pixels(pixels as u32))
+        }
+        else {
+            None
+        }
+    }
+    else if unit == "%" || unit.chars().all(|c| c.is_alphabetic()) {
+        Some(Size::Other(Dimension {
+            value: number.to_string(),
+            unit: unit,
+        }))
+    }
+    else {
+        None
+    }
+}
+
+/// Value of the last declaration of a property in an inline `style` attribute.
+fn style_property<'a>(style: &'a str, name: &str) -> Option<&'a str> {
+    style.split(';')
+        .filter_map(|declaration| {
+            let mut parts = declaration.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(property), Some(value)) if property.trim().to_lowercase() == name => {
+                    Some(value.trim().trim_right_matches("!important").trim())
+                }
+                _ => None,
+            }
+        })
+        .last()
+}
+
+/// Size of an image along one axis, read from its inline style or else from its attribute. An
+/// unparseable value is reported in `errors` and ignored.
+fn image_size(image: &kuchiki::NodeDataRef<kuchiki::ElementData>,
+              kind: SelectorKind,
+              property: &'static str,
+              errors: &mut Vec<Error>)
+              -> (Option<u32>, Option<Dimension>) {
+    let attributes = image.attributes.borrow();
+    let style_value = attributes.get("style").and_then(|style| style_property(style, property));
+    let value = match style_value {
+        // Keywords like `auto` or `inherit` don't tell anything about the size
+        Some(value) if !value.chars().all(|c| c.is_alphabetic()) => value,
+        _ => {
+            match attributes.get(property) {
+                Some(value) if !value.trim().is_empty() => value,
+                _ => return (None, None),
+            }
+        }
+    };
+
+    match parse_size(value) {
+        Some(Size::Pixels(pixels)) => (Some(pixels), None),
+        Some(Size::Other(dimension)) => (None, Some(dimension)),
+        None => {
+            errors.push(Error::InvalidDimension {
+                kind: kind,
+                element: Error::describe_element(image),
+                property: property,
+                value: value.to_string(),
+                context: ErrorContext::default(),
+            });
+            (None, None)
+        }
+    }
+}
+
+/// Largest `colspan` of a table cell, as defined by HTML.
+const MAX_COLSPAN: u32 = 1000;
+/// Largest `rowspan` of a table cell, as defined by HTML.
+const MAX_ROWSPAN: u32 = 65534;
+
+/// Text of a metadata element: the `content` attribute of a `<meta>` element, or else the text of
+/// its children.
+fn metadata_text(node: &kuchiki::NodeDataRef<kuchiki::ElementData>, children: Vec<Part>) -> String {
+    let content = node.attributes.borrow().get("content").map(String::from);
+    match content {
+        Some(content) => Part::Text(content).normalized_text(),
+        None => Part::Paragraph(children).normalized_text(),
+    }
+}
+
+/// Kind of the media embedded by an element. A `<source>` element gets the kind of its parent.
+fn media_kind(element: &kuchiki::NodeDataRef<kuchiki::ElementData>) -> EmbedKind {
+    let kind_of = |node: &kuchiki::NodeRef| {
+        if is_element(node, "audio") {
+            Some(EmbedKind::Audio)
+        }
+        else if is_element(node, "video") {
+            Some(EmbedKind::Video)
+        }
+        else {
+            None
+        }
+    };
+    let node = element.as_node();
+    if let Some(kind) = kind_of(node) {
+        return kind;
+    }
+    if is_element(node, "source") {
+        return node.parent().and_then(|parent| kind_of(&parent)).unwrap_or(EmbedKind::Video);
+    }
+    EmbedKind::Frame
+}
+
 fn text(mut content: Vec<Part>) -> String {
     content.drain(..)
         .filter_map(|part| {
@@ -186,46 +591,121 @@ impl Extractor {
 }
 
 impl Extractor {
-    pub fn extract(&self, root: &kuchiki::NodeRef) -> Vec<Document> {
+    /// Extracts documents from a DOM, each one along with the issues encountered while extracting
+    /// it. Relative URLs are resolved against `url` and the `<base>` element of the DOM, relative
+    /// dates against `now`. Relative dates can't be parsed without `now`.
+    pub fn extract(&self,
+                   root: &kuchiki::NodeRef,
+                   url: Option<&Url>,
+                   now: Option<&chrono::DateTime<chrono::FixedOffset>>)
+                   -> Vec<(Document, Report)> {
+        let base_url = self.base_url(root, url);
         let mut documents = Vec::new();
-        self.extract_rec(root, &mut documents);
+        self.extract_rec(root, base_url.as_ref(), now, &mut documents);
+        // Page metadata can't be attributed when the page holds several documents
+        if self.options.metadata_fallback && documents.len() == 1 {
+            self.complete_document(&mut documents[0].0, root);
+        }
         documents
     }
 
-    fn extract_rec(&self, root: &kuchiki::NodeRef, documents: &mut Vec<Document>) {
+    /// Extracts a single document from `content`, a node of the page `root`, ignoring the root
+    /// selector.
+    pub fn extract_content(&self,
+                           root: &kuchiki::NodeRef,
+                           content: &kuchiki::NodeRef,
+                           url: Option<&Url>,
+                           now: Option<&chrono::DateTime<chrono::FixedOffset>>)
+                           -> (Document, Report) {
+        let base_url = self.base_url(root, url);
+        let (mut document, report) = self.extract_document(content, base_url.as_ref(), now);
+        if self.options.metadata_fallback {
+            self.complete_document(&mut document, root);
+        }
+        (document, report)
+    }
+
+    /// Completes a document with the page metadata, except the fields the selectors look for.
+    fn complete_document(&self, document: &mut Document, root: &kuchiki::NodeRef) {
+        let selected: Vec<SelectorKind> =
+            self.selectors.iter().map(|selector| selector.kind).collect();
+        metadata::complete_document(document, root, &selected);
+    }
+
+    fn base_url(&self, root: &kuchiki::NodeRef, url: Option<&Url>) -> Option<Url> {
+        let base_href = root.select("base[href]")
+            .ok()
+            .and_then(|mut elements| elements.next())
+            .and_then(|base| {
+                let href = base.attributes.borrow().get("href").map(|href| href.to_string());
+                href
+            });
+
+        match (url, base_href) {
+            (Some(url), Some(href)) => url.join(&href).ok().or_else(|| Some(url.clone())),
+            (None, Some(href)) => Url::parse(&href).ok(),
+            (url, None) => url.cloned(),
+        }
+    }
+
+    fn resolve_url(&self, url: &str, base_url: Option<&Url>) -> String {
+        match base_url {
+            Some(base_url) if !self.options.keep_raw_urls => {
+                base_url.join(url).map(|u| u.into_string()).unwrap_or_else(|_| url.to_string())
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    fn extract_rec(&self,
+                   root: &kuchiki::NodeRef,
+                   base_url: Option<&Url>,
+                   now: Option<&chrono::DateTime<chrono::FixedOffset>>,
+                   documents: &mut Vec<(Document, Report)>) {
         if let Some(ref root_selector) = self.options.root_selector {
 
             if let Some(root_element) = root.clone().into_element_ref() {
                 if root_selector.matches(&root_element) {
-                    documents.push(self.extract_document(root));
+                    documents.push(self.extract_document(root, base_url, now));
                     return;
                 }
             }
 
             for child in root.children() {
-                self.extract_rec(&child, documents);
+                self.extract_rec(&child, base_url, now, documents);
             }
 
         }
         else {
-            documents.push(self.extract_document(root));
+            documents.push(self.extract_document(root, base_url, now));
         }
     }
 
-    fn extract_document(&self, root: &kuchiki::NodeRef) -> Document {
+    fn extract_document(&self,
+                        root: &kuchiki::NodeRef,
+                        base_url: Option<&Url>,
+                        now: Option<&chrono::DateTime<chrono::FixedOffset>>)
+                        -> (Document, Report) {
         let mut document = Document::default();
+        let mut report = Report::default();
         {
             let mut result = ExtractorResult {
                 document: &mut document,
+                report: &mut report,
+                base_url: base_url,
+                now: now,
                 parent_children: Vec::new(),
+                in_blockquote: false,
+                attribution: None,
+                errors: Vec::new(),
             };
             self.extract_document_rec(root, &mut result);
             result.document.content.append(&mut result.parent_children);
         }
-        document
+        (document, report)
     }
 
-    fn extract_document_rec(&self, root: &kuchiki::NodeRef, mut result: &mut ExtractorResult) {
+    fn extract_document_rec(&self, root: &kuchiki::NodeRef, result: &mut ExtractorResult) {
         let ignore_text = {
             if let Some(ref el) = root.as_element() {
                 el.name.local.eq_str_ignore_ascii_case("script") ||
@@ -237,114 +717,525 @@ impl Extractor {
         };
 
         for child in root.children() {
-            if let Some(ref child_element) = child.clone().into_element_ref() {
-                let mut is_node = false;
-                for selector in &self.selectors {
-                    if selector.query.matches(child_element) {
-                        let mut children = Vec::new();
+            self.extract_node(&child, ignore_text, result);
+        }
+    }
 
-                        mem::swap(&mut result.parent_children, &mut children);
-                        self.extract_document_rec(&child, &mut result);
-                        mem::swap(&mut result.parent_children, &mut children);
+    fn extract_node(&self,
+                    child: &kuchiki::NodeRef,
+                    ignore_text: bool,
+                    mut result: &mut ExtractorResult) {
+        if let Some(ref child_element) = child.clone().into_element_ref() {
+            if self.options.exclude_selectors.iter().any(|s| s.matches(child_element)) {
+                return;
+            }
+
+            let mut is_node = false;
+            for selector in &self.selectors {
+                if selector.query.matches(child_element) {
+                    let mut children = Vec::new();
 
-                        if let Err(error) = self.handle_part(&selector.kind,
-                                                             child_element,
-                                                             children,
-                                                             &mut result) {
+                    let outer_blockquote = if selector.kind == SelectorKind::Blockquote {
+                        Some((mem::replace(&mut result.in_blockquote, true),
+                              result.attribution.take()))
+                    }
+                    else {
+                        None
+                    };
 
-                            if let Some(ref f) = self.options.on_parse_error {
-                                f(error);
+                    let value = match selector.attribute {
+                        Some(ref attribute) => {
+                            let value = child_element.attributes
+                                .borrow()
+                                .get(&attribute[..])
+                                .map(String::from);
+                            match value {
+                                Some(value) => Ok(Some(value)),
+                                None => {
+                                    Err(Error::MissingAttribute {
+                                        kind: selector.kind,
+                                        element: Error::describe_element(child_element),
+                                        attribute: attribute.clone(),
+                                        context: ErrorContext::default(),
+                                    })
+                                }
                             }
                         }
-                        is_node = true;
-                        break;
+                        None => Ok(None),
+                    };
+
+                    // Links, images and media can't do without their URL, other parts fall back
+                    // to their content
+                    let (value, missing_attribute) = match value {
+                        Err(error) => {
+                            match selector.kind {
+                                SelectorKind::Image |
+                                SelectorKind::Link |
+                                SelectorKind::Media => (Err(error), None),
+                                _ => (Ok(None), Some(error)),
+                            }
+                        }
+                        value => (value, None),
+                    };
+
+                    match (selector.kind, &value) {
+                        // Walked by handle_part to keep their structure
+                        (SelectorKind::Figure, _) |
+                        (SelectorKind::Language, _) |
+                        (SelectorKind::Media, _) |
+                        (SelectorKind::Table, _) |
+                        (_, &Err(_)) => {}
+                        // The attribute replaces the content, except for links where it is the URL
+                        (kind, &Ok(Some(ref value))) if kind != SelectorKind::Link => {
+                            children.push(Part::Text(value.clone()))
+                        }
+                        _ => {
+                            mem::swap(&mut result.parent_children, &mut children);
+                            self.extract_document_rec(child, &mut result);
+                            mem::swap(&mut result.parent_children, &mut children);
+                        }
+                    }
+
+                    let handled = match value {
+                        Ok(value) => {
+                            self.handle_part(&selector.kind,
+                                             child_element,
+                                             children,
+                                             value,
+                                             &mut result)
+                        }
+                        Err(error) => Err(error),
+                    };
+
+                    if let Some((in_blockquote, attribution)) = outer_blockquote {
+                        result.in_blockquote = in_blockquote;
+                        result.attribution = attribution;
+                    }
+
+                    let errors = mem::replace(&mut result.errors, Vec::new());
+                    for error in missing_attribute.into_iter().chain(handled.err()).chain(errors) {
+                        result.report.warnings.push(Warning {
+                            kind: selector.kind,
+                            selector: selector.source.clone(),
+                            path: dom_path(child),
+                            error: error,
+                        });
                     }
+                    is_node = true;
+                    break;
+                }
+            }
+
+            if !is_node {
+                self.extract_document_rec(child, result);
+            }
+        }
+        else if !ignore_text {
+            let text = child.text_contents();
+            if !text.is_empty() {
+                let mut appended = false;
+                if let Some(&mut Part::Text(ref mut last)) = result.parent_children.last_mut() {
+                    appended = true;
+                    last.push_str(&text);
                 }
 
-                if !is_node {
-                    self.extract_document_rec(&child, result);
+                if !appended {
+                    result.parent_children.push(Part::Text(text));
                 }
             }
-            else if !ignore_text {
-                let text = child.text_contents();
-                if !text.is_empty() {
-                    let mut appended = false;
-                    if let Some(&mut Part::Text(ref mut last)) = result.parent_children.last_mut() {
-                        appended = true;
-                        last.push_str(&text);
-                    }
+        }
+    }
 
-                    if !appended {
-                        result.parent_children.push(Part::Text(text));
-                    }
+    fn extract_table_rows(&self,
+                          section: &kuchiki::NodeRef,
+                          rows: &mut Vec<Vec<TableCell>>,
+                          mut result: &mut ExtractorResult) {
+        for row in section.children().filter(|node| is_element(node, "tr")) {
+            let mut cells = Vec::new();
+            for cell in row.children() {
+                let header = is_element(&cell, "th");
+                if !header && !is_element(&cell, "td") {
+                    continue;
                 }
+
+                let (colspan, rowspan) = {
+                    let element = cell.as_element().unwrap();
+                    let attributes = element.attributes.borrow();
+                    // Clamped like browsers do, 0 being read as 1
+                    let span = |name: &str, max: u32| {
+                        attributes.get(name)
+                            .and_then(|value| value.trim().parse::<u64>().ok())
+                            .map(|span| cmp::max(1, cmp::min(span, max as u64)) as u32)
+                            .unwrap_or(1)
+                    };
+                    (span("colspan", MAX_COLSPAN), span("rowspan", MAX_ROWSPAN))
+                };
+
+                let mut content = Vec::new();
+                mem::swap(&mut result.parent_children, &mut content);
+                self.extract_document_rec(&cell, &mut result);
+                mem::swap(&mut result.parent_children, &mut content);
+
+                cells.push(TableCell {
+                    header: header,
+                    colspan: colspan,
+                    rowspan: rowspan,
+                    content: content,
+                });
+            }
+            rows.push(cells);
+        }
+    }
+
+    fn extract_table(&self, table: &kuchiki::NodeRef, mut result: &mut ExtractorResult) -> Part {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+
+        self.extract_table_rows(table, &mut body, &mut result);
+        for section in table.children() {
+            if is_element(&section, "thead") {
+                self.extract_table_rows(&section, &mut header, &mut result);
             }
+            else if is_element(&section, "tbody") || is_element(&section, "tfoot") {
+                self.extract_table_rows(&section, &mut body, &mut result);
+            }
+        }
+
+        // Without <thead>, leading rows made of <th> cells are header rows
+        if header.is_empty() {
+            let count = body.iter()
+                .take_while(|row| !row.is_empty() && row.iter().all(|cell| cell.header))
+                .count();
+            header = body.drain(..count).collect();
         }
 
+        Part::Table {
+            header: header,
+            body: body,
+        }
     }
 
-    fn parse_date(&self, content: Vec<Part>) -> Result<chrono::NaiveDate, Box<error::Error>> {
-        if let Some(ref format) = self.options.date_format {
-            Ok(parse_date(format, &text(content))?)
+    fn parse_date(&self,
+                  selector_kind: &SelectorKind,
+                  node: &kuchiki::ElementData,
+                  content: Vec<Part>,
+                  value: Option<String>,
+                  now: Option<&chrono::DateTime<chrono::FixedOffset>>)
+                  -> Result<PartDate, Error> {
+        let from_selector = value.is_some();
+        let attribute = match value {
+            // Attributes picked by the selector may be human-readable, unlike the date attributes
+            Some(value) => Some(translate_date(self.options.date_locale, value.trim())),
+            None => {
+                let attributes = node.attributes.borrow();
+                let date = self.options
+                    .date_attributes
+                    .iter()
+                    .filter_map(|name| attributes.get(&name[..]))
+                    .map(|value| value.trim().to_string())
+                    .find(|value| !value.is_empty());
+                date
+            }
+        };
+
+        if let Some(value) = attribute {
+            // Machine-readable values are usually ISO 8601
+            let date = parse_auto_date(self.options.date_order, &value).or_else(|error| {
+                if self.options.date_formats.is_empty() {
+                    Err(error)
+                }
+                else {
+                    parse_date_formats(&self.options.date_formats,
+                                       self.options.date_order,
+                                       now,
+                                       &value)
+                }
+            });
+            // Date attributes holding something else, like the duration of a <time>, fall back to
+            // the content of the element
+            if date.is_ok() || from_selector || self.options.date_formats.is_empty() {
+                return date;
+            }
+        }
+
+        if self.options.date_formats.is_empty() {
+            Err(Error::NoDateFormat {
+                kind: *selector_kind,
+                element: Error::describe_element(node),
+                context: ErrorContext::default(),
+            })
         }
         else {
-            Err("No date format")?
+            parse_date_formats(&self.options.date_formats,
+                               self.options.date_order,
+                               now,
+                               &translate_date(self.options.date_locale, &text(content)))
         }
     }
 
     fn handle_part(&self,
                    selector_kind: &SelectorKind,
-                   node: &kuchiki::ElementData,
+                   node: &kuchiki::NodeDataRef<kuchiki::ElementData>,
                    children: Vec<Part>,
+                   value: Option<String>,
                    mut result: &mut ExtractorResult)
-                   -> Result<(), Box<error::Error>> {
+                   -> Result<(), Error> {
 
-        macro_rules! get_attr {
-            ($node:expr, $name:expr) => (
-                if let Some(attr) = $node.attributes.borrow().get($name) {
-                    attr.parse().ok() // TODO handle error, handle XXXpx format
-                }
-                else {
-                    None
+        macro_rules! missing_attr {
+            ($attr:expr) => (
+                || Error::MissingAttribute {
+                    kind: *selector_kind,
+                    element: Error::describe_element(node),
+                    attribute: $attr.to_string(),
+                    context: ErrorContext::default(),
                 }
             )
         }
 
         match *selector_kind {
+            SelectorKind::Attribution => {
+                if result.in_blockquote {
+                    let mut attribution = result.attribution.take().unwrap_or_else(Vec::new);
+                    attribution.extend(children);
+                    result.attribution = Some(attribution);
+                }
+                else {
+                    result.parent_children.extend(children);
+                }
+            }
+            SelectorKind::Author => {
+                let author = match value {
+                    Some(value) => Part::Text(value).normalized_text(),
+                    None => metadata_text(node, children),
+                };
+                if !author.is_empty() && !result.document.authors.contains(&author) {
+                    result.document.authors.push(author);
+                }
+            }
+            SelectorKind::Blockquote => {
+                let cite = node.attributes
+                    .borrow()
+                    .get("cite")
+                    .map(|cite| self.resolve_url(cite, result.base_url));
+                result.parent_children.push(Part::Blockquote {
+                    cite: cite,
+                    content: children,
+                    attribution: result.attribution.take(),
+                })
+            }
+            SelectorKind::CanonicalUrl => {
+                let href = value.or_else(|| node.attributes.borrow().get("href").map(String::from));
+                let url = href.unwrap_or_else(|| metadata_text(node, children));
+                if !url.trim().is_empty() {
+                    result.document.canonical_url =
+                        Some(self.resolve_url(url.trim(), result.base_url));
+                }
+            }
+            SelectorKind::CodeBlock => {
+                result.parent_children.push(Part::CodeBlock {
+                    language: code_language(node),
+                    code: full_text(&children),
+                })
+            }
             SelectorKind::Date => {
-                result.parent_children.push(Part::Date(self.parse_date(children)?))
+                let date = self.parse_date(selector_kind, node, children, value, result.now)?;
+                result.parent_children.push(Part::Date(date))
             }
             SelectorKind::Emphasis => result.parent_children.push(Part::Emphasis(children)),
             SelectorKind::Header1 => result.parent_children.push(Part::Header1(children)),
             SelectorKind::Header2 => result.parent_children.push(Part::Header2(children)),
             SelectorKind::Header3 => result.parent_children.push(Part::Header3(children)),
+            SelectorKind::Figure => {
+                let mut content = Vec::new();
+                let mut caption = None;
+                mem::swap(&mut result.parent_children, &mut content);
+                for child in node.as_node().children() {
+                    if is_element(&child, "figcaption") {
+                        let mut caption_children = Vec::new();
+                        mem::swap(&mut result.parent_children, &mut caption_children);
+                        self.extract_document_rec(&child, &mut result);
+                        mem::swap(&mut result.parent_children, &mut caption_children);
+                        caption = Some(caption_children);
+                    }
+                    else {
+                        self.extract_node(&child, false, &mut result);
+                    }
+                }
+                mem::swap(&mut result.parent_children, &mut content);
+                result.parent_children.push(Part::Figure {
+                    content: content,
+                    caption: caption,
+                })
+            }
             SelectorKind::Image => {
+                // The selector may match a <picture> element
+                let image = if node.name.local.eq_str_ignore_ascii_case("picture") {
+                    node.as_node()
+                        .select("img")
+                        .ok()
+                        .and_then(|mut images| images.next())
+                        .ok_or_else(missing_attr!("src"))?
+                }
+                else {
+                    node.clone()
+                };
+                let source = value.or_else(|| image_source(&image))
+                    .ok_or_else(missing_attr!("src"))?;
+                let (width, css_width) =
+                    image_size(&image, *selector_kind, "width", &mut result.errors);
+                let (height, css_height) =
+                    image_size(&image, *selector_kind, "height", &mut result.errors);
+                let attributes = image.attributes.borrow();
                 result.parent_children.push(Part::Image {
-                    url: node.attributes
-                        .borrow()
-                        .get("src")
-                        .ok_or_else(|| "The image tag has no src attribute")?
-                        .to_string(),
-                    legend: node.attributes.borrow().get("title").map(|s| s.to_string()),
-                    width: get_attr!(node, "width"),
-                    height: get_attr!(node, "height"),
+                    url: self.resolve_url(&source, result.base_url),
+                    legend: attributes.get("title").map(|s| s.to_string()),
+                    alt: attributes.get("alt").map(|s| s.to_string()),
+                    width: width,
+                    height: height,
+                    css_width: css_width,
+                    css_height: css_height,
                 })
             }
+            SelectorKind::InlineCode => {
+                result.parent_children.push(Part::InlineCode(full_text(&children)))
+            }
+            SelectorKind::Language => {
+                let lang = value.or_else(|| {
+                    let attributes = node.attributes.borrow();
+                    let lang = attributes.get("lang").or_else(|| attributes.get("content"));
+                    lang.map(String::from)
+                });
+                // Elements like <html lang="en"> wrap the content to extract
+                self.extract_document_rec(node.as_node(), &mut result);
+                match lang {
+                    Some(lang) => {
+                        let language = Part::Text(lang).normalized_text();
+                        if !language.is_empty() {
+                            result.document.language = Some(language);
+                        }
+                    }
+                    None => {
+                        let missing_lang = missing_attr!("lang");
+                        result.errors.push(missing_lang())
+                    }
+                }
+            }
             SelectorKind::Link => {
+                let href = value.or_else(|| node.attributes.borrow().get("href").map(String::from))
+                    .ok_or_else(missing_attr!("href"))?;
                 result.parent_children.push(Part::Link {
-                    url: node.attributes
-                        .borrow()
-                        .get("href")
-                        .ok_or_else(|| "The link has no href attribute")?
-                        .to_string(),
+                    url: self.resolve_url(&href, result.base_url),
+                    content: children,
+                })
+            }
+            SelectorKind::List if !node.name.local.eq_str_ignore_ascii_case("ol") => {
+                result.parent_children.push(Part::List(children))
+            }
+            SelectorKind::List |
+            SelectorKind::OrderedList => {
+                let start = node.attributes
+                    .borrow()
+                    .get("start")
+                    .and_then(|start| start.trim().parse::<i64>().ok())
+                    .unwrap_or(1);
+                result.parent_children.push(Part::OrderedList {
+                    start: start,
                     content: children,
                 })
             }
-            SelectorKind::List => result.parent_children.push(Part::List(children)),
             SelectorKind::ListItem => result.parent_children.push(Part::ListItem(children)),
+            SelectorKind::Media => {
+                let kind = media_kind(node);
+                let attributes = node.attributes.borrow();
+                let url = match value.as_ref().map(String::as_str).or(attributes.get("src")) {
+                    Some(src) if !src.trim().is_empty() => src.trim().to_string(),
+                    _ => {
+                        // <video> and <audio> elements may list their sources as children
+                        node.as_node()
+                            .children()
+                            .filter(|child| is_element(child, "source"))
+                            .filter_map(|source| {
+                                let src = source.as_element()
+                                    .unwrap()
+                                    .attributes
+                                    .borrow()
+                                    .get("src")
+                                    .map(|src| src.trim().to_string());
+                                src
+                            })
+                            .find(|src| !src.is_empty())
+                            .ok_or_else(missing_attr!("src"))?
+                    }
+                };
+                let url = self.resolve_url(&url, result.base_url);
+                if !is_safe_url(&url) {
+                    return Err(Error::UnsafeUrl {
+                        kind: *selector_kind,
+                        element: Error::describe_element(node),
+                        url: url,
+                        context: ErrorContext::default(),
+                    });
+                }
+                // A <source> element uses the poster of its parent
+                let poster = attributes.get("poster")
+                    .map(|poster| poster.to_string())
+                    .or_else(|| {
+                        node.as_node()
+                            .parent()
+                            .and_then(|parent| parent.into_element_ref())
+                            .and_then(|parent| {
+                                let poster =
+                                    parent.attributes.borrow().get("poster").map(String::from);
+                                poster
+                            })
+                    })
+                    .map(|poster| self.resolve_url(&poster, result.base_url));
+                let poster = match poster {
+                    Some(ref poster) if !is_safe_url(poster) => {
+                        result.errors.push(Error::UnsafeUrl {
+                            kind: *selector_kind,
+                            element: Error::describe_element(node),
+                            url: poster.clone(),
+                            context: ErrorContext::default(),
+                        });
+                        None
+                    }
+                    poster => poster,
+                };
+                result.parent_children.push(Part::Embed {
+                    kind: kind,
+                    url: url,
+                    poster: poster,
+                    title: attributes.get("title")
+                        .or_else(|| attributes.get("aria-label"))
+                        .map(|title| title.to_string()),
+                })
+            }
             SelectorKind::Paragraph => result.parent_children.push(Part::Paragraph(children)),
             SelectorKind::PublicationDate => {
-                result.document.publication_date = Some(self.parse_date(children)?)
+                result.document.publication_date =
+                    Some(self.parse_date(selector_kind, node, children, value, result.now)?)
+            }
+            SelectorKind::Summary => {
+                let content =
+                    value.or_else(|| node.attributes.borrow().get("content").map(String::from));
+                result.document.summary = Some(match content {
+                    Some(content) => vec![Part::Text(content)],
+                    None => children,
+                })
+            }
+            SelectorKind::Table => {
+                let table = self.extract_table(node.as_node(), &mut result);
+                result.parent_children.push(table)
+            }
+            SelectorKind::Tag => {
+                let tag = match value {
+                    Some(value) => Part::Text(value).normalized_text(),
+                    None => metadata_text(node, children),
+                };
+                if !tag.is_empty() && !result.document.tags.contains(&tag) {
+                    result.document.tags.push(tag);
+                }
             }
             SelectorKind::Title => result.document.title = Some(children),
         }
@@ -356,26 +1247,43 @@ impl Extractor {
 #[cfg(test)]
 mod extractor {
     use ::extractor::{Extractor, ExtractorOptions, Selector, SelectorKind};
-    use ::part::{Part, Document};
+    use ::date::{DateFormat, DateLocale};
+    use ::part::{Dimension, EmbedKind, Part, Document, TableCell};
+    use ::report::Report;
+    use ::error::Error;
     use ::kuchiki;
     use ::chrono;
+    use ::url::Url;
+    use chrono::TimeZone;
     use kuchiki::traits::TendrilSink;
 
-    fn extract_markup(selectors: Vec<Selector>,
-                      markup: &str,
-                      mut options: ExtractorOptions)
-                      -> Document {
-
-        if options.on_parse_error.is_none() {
-            options.on_parse_error = Some(Box::new(|error| panic!("{}", error)));
-        }
+    fn pattern(format: &str) -> DateFormat {
+        DateFormat::Pattern(format.parse().unwrap())
+    }
 
+    fn extract_markup_with_report(selectors: Vec<Selector>,
+                                  markup: &str,
+                                  options: ExtractorOptions)
+                                  -> (Document, Report) {
         let mut extractor = Extractor::new(options);
         for selector in selectors {
             extractor.add_selector(selector);
         }
         let root = kuchiki::parse_html().one(markup);
-        extractor.extract(&root).pop().unwrap()
+        let url = Url::parse("http://example.com/blog/post.html").unwrap();
+        let now = chrono::FixedOffset::east(0).ymd(2016, 5, 17).and_hms(12, 0, 0);
+        extractor.extract(&root, Some(&url), Some(&now)).pop().unwrap()
+    }
+
+    fn extract_markup(selectors: Vec<Selector>,
+                      markup: &str,
+                      options: ExtractorOptions)
+                      -> Document {
+        let (document, report) = extract_markup_with_report(selectors, markup, options);
+        if let Some(warning) = report.warnings.first() {
+            panic!("{}", warning);
+        }
+        document
     }
 
     #[test]
@@ -396,6 +1304,7 @@ mod extractor {
                        title: Some(vec![Part::Text("Hi!".to_string())]),
                        publication_date: None,
                        content: vec![Part::Text("\n\n    \n    ab  c\n".to_string())],
+                       ..Document::default()
                    });
     }
 
@@ -410,7 +1319,7 @@ mod extractor {
                                                          ".date".parse().unwrap())],
                                       markup,
                                       ExtractorOptions {
-                                          date_format: Some("%Y-%m-%d".to_string()),
+                                          date_formats: vec![pattern("%Y-%m-%d")],
                                           ..ExtractorOptions::default()
                                       });
 
@@ -419,8 +1328,9 @@ mod extractor {
                        title: None,
                        publication_date: None,
                        content: vec![Part::Text("\n\n    Hi!\n    ".to_string()),
-                                     Part::Date(chrono::NaiveDate::from_ymd(2015, 10, 10)),
+                                     Part::Date(chrono::NaiveDate::from_ymd(2015, 10, 10).into()),
                                      Part::Text("\n".to_string())],
+                       ..Document::default()
                    });
     }
 
@@ -435,18 +1345,604 @@ mod extractor {
                                                          ".date".parse().unwrap())],
                                       markup,
                                       ExtractorOptions {
-                                          date_format: Some("%Y-%m-%d".to_string()),
+                                          date_formats: vec![pattern("%Y-%m-%d")],
                                           ..ExtractorOptions::default()
                                       });
 
         assert_eq!(document,
                    Document {
                        title: None,
-                       publication_date: Some(chrono::NaiveDate::from_ymd(2015, 10, 10)),
+                       publication_date: Some(chrono::NaiveDate::from_ymd(2015, 10, 10).into()),
                        content: vec![Part::Text("\n\n    Hi!\n    \n".to_string())],
+                       ..Document::default()
+                   });
+    }
+
+    #[test]
+    fn test_relative_publication_date() {
+        let markup = r#"<html><body><span class="date">Posted 3 hours ago</span></body></html>"#;
+        let document = extract_markup(vec![Selector::new(SelectorKind::PublicationDate,
+                                                         ".date".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions {
+                                          date_formats: vec![DateFormat::Relative],
+                                          ..ExtractorOptions::default()
+                                      });
+
+        assert_eq!(document.publication_date.unwrap().to_string(),
+                   "2016-05-17T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_date_attributes() {
+        let markup = r#"<html><body>
+            <time datetime="2015-10-10T08:30:00+02:00">Yesterday</time>
+            <span data-date="2015-10-11">Today</span>
+            <span data-published="2015-10-12" class="custom">Tomorrow</span>
+        </body></html>"#;
+        let selectors = || vec![Selector::new(SelectorKind::Date, "time, span".parse().unwrap())];
+        let dates = |document: Document| -> Vec<String> {
+            document.content
+                .iter()
+                .filter_map(|part| {
+                    match *part {
+                        Part::Date(ref date) => Some(date.to_string()),
+                        _ => None,
+                    }
+                })
+                .collect()
+        };
+
+        let (document, report) =
+            extract_markup_with_report(selectors(), markup, ExtractorOptions::default());
+        assert_eq!(dates(document),
+                   vec!["2015-10-10T08:30:00+02:00".to_string(), "2015-10-11".to_string()]);
+        assert_eq!(report.warnings.len(), 1);
+
+        let (document, _) =
+            extract_markup_with_report(selectors(),
+                                       markup,
+                                       ExtractorOptions {
+                                           date_attributes: vec!["data-published".to_string()],
+                                           date_formats: vec![DateFormat::Auto],
+                                           ..ExtractorOptions::default()
+                                       });
+        assert_eq!(dates(document), vec!["2015-10-12".to_string()]);
+    }
+
+    #[test]
+    fn test_translated_date_attribute() {
+        let markup = r#"<html><body><span title="3 janv. 2016">hier</span></body></html>"#;
+        let document = extract_markup(vec![Selector::new(SelectorKind::PublicationDate,
+                                                         "span".parse().unwrap())
+                                               .attribute("title".to_string())],
+                                      markup,
+                                      ExtractorOptions {
+                                          date_formats: vec![pattern("%d %B %Y")],
+                                          date_locale: DateLocale::French,
+                                          ..ExtractorOptions::default()
+                                      });
+
+        assert_eq!(document.publication_date.unwrap().to_string(), "2016-01-03");
+    }
+
+    #[test]
+    fn test_invalid_date_attribute() {
+        let markup = r#"<html><body><time datetime="PT2M">16 May 2016</time></body></html>"#;
+        let document = extract_markup(vec![Selector::new(SelectorKind::PublicationDate,
+                                                         "time".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions {
+                                          date_formats: vec![pattern("%d %B %Y")],
+                                          ..ExtractorOptions::default()
+                                      });
+
+        assert_eq!(document.publication_date.unwrap().to_string(), "2016-05-16");
+    }
+
+    #[test]
+    fn test_report() {
+        let markup = r#"<DOCTYPE html>
+<html>
+    <body><p class="intro text"><img id="logo"></p></body>
+</html>"#;
+        let (_, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Image,
+                                                          "img".parse().unwrap())
+                                                .source("img".to_string())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        assert_eq!(report.warnings.len(), 1);
+        let warning = &report.warnings[0];
+        assert_eq!(warning.kind, SelectorKind::Image);
+        assert_eq!(warning.selector, Some("img".to_string()));
+        assert_eq!(warning.path, "html > body > p.intro.text > img#logo");
+        match warning.error {
+            Error::MissingAttribute { ref attribute, .. } => assert_eq!(attribute, "src"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_url_resolution() {
+        let markup = r#"<html><body><a href="other.html">a</a><img src="/logo.png"></body></html>"#;
+        let selectors = || {
+            vec![Selector::new(SelectorKind::Link, "a".parse().unwrap()),
+                 Selector::new(SelectorKind::Image, "img".parse().unwrap())]
+        };
+
+        let document = extract_markup(selectors(), markup, ExtractorOptions::default());
+        assert_eq!(document.content,
+                   vec![Part::Link {
+                            url: "http://example.com/blog/other.html".to_string(),
+                            content: vec![Part::Text("a".to_string())],
+                        },
+                        Part::Image {
+                            url: "http://example.com/logo.png".to_string(),
+                            width: None,
+                            height: None,
+                            legend: None,
+                            alt: None,
+                            css_width: None,
+                            css_height: None,
+                        }]);
+
+        let document = extract_markup(selectors(),
+                                      markup,
+                                      ExtractorOptions {
+                                          keep_raw_urls: true,
+                                          ..ExtractorOptions::default()
+                                      });
+        assert_eq!(document.content[0],
+                   Part::Link {
+                       url: "other.html".to_string(),
+                       content: vec![Part::Text("a".to_string())],
+                   });
+    }
+
+    #[test]
+    fn test_url_resolution_with_base() {
+        let markup = "<html><head><base href=\"/doc/\"></head>\
+                      <body><a href=\"a.html\">a</a></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Link,
+                                                         "a".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+        assert_eq!(document.content[0],
+                   Part::Link {
+                       url: "http://example.com/doc/a.html".to_string(),
+                       content: vec![Part::Text("a".to_string())],
                    });
     }
 
+    #[test]
+    fn test_exclude() {
+        let markup = "<html><body><p>a<span class=\"ad\">b</span></p>\
+                      <div class=\"share\"><p>c</p></div></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Paragraph,
+                                                         "p".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions {
+                                          exclude_selectors: vec![".ad, .share"
+                                                                      .parse()
+                                                                      .unwrap()],
+                                          ..ExtractorOptions::default()
+                                      });
+
+        assert_eq!(document.content,
+                   vec![Part::Paragraph(vec![Part::Text("a".to_string())])]);
+    }
+
+    #[test]
+    fn test_code() {
+        let markup = "<html><body><pre class=\"highlight\"><code class=\"language-rust\">\
+                      fn main() {\n    <em>println!</em>(\"hi\");\n}\n</code></pre>\
+                      <p>Use <code>  cargo  </code></p></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::CodeBlock,
+                                                         "pre".parse().unwrap()),
+                                           Selector::new(SelectorKind::InlineCode,
+                                                         "code".parse().unwrap()),
+                                           Selector::new(SelectorKind::Emphasis,
+                                                         "em".parse().unwrap()),
+                                           Selector::new(SelectorKind::Paragraph,
+                                                         "p".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+
+        assert_eq!(document.content,
+                   vec![Part::CodeBlock {
+                            language: Some("rust".to_string()),
+                            code: "fn main() {\n    println!(\"hi\");\n}\n".to_string(),
+                        },
+                        Part::Paragraph(vec![Part::Text("Use ".to_string()),
+                                             Part::InlineCode("  cargo  ".to_string())])]);
+    }
+
+    #[test]
+    fn test_blockquote() {
+        let markup = "<html><body><blockquote cite=\"/source\"><p>Quote</p>\
+                      <footer>Someone</footer></blockquote><footer>Not</footer></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Blockquote,
+                                                         "blockquote".parse().unwrap()),
+                                           Selector::new(SelectorKind::Attribution,
+                                                         "footer".parse().unwrap()),
+                                           Selector::new(SelectorKind::Paragraph,
+                                                         "p".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+
+        assert_eq!(document.content,
+                   vec![Part::Blockquote {
+                            cite: Some("http://example.com/source".to_string()),
+                            content: vec![Part::Paragraph(vec![Part::Text("Quote"
+                                                                   .to_string())])],
+                            attribution: Some(vec![Part::Text("Someone".to_string())]),
+                        },
+                        Part::Text("Not".to_string())]);
+    }
+
+    #[test]
+    fn test_table() {
+        let markup = "<html><body><table>\
+                      <tr><th>A</th><th>B</th></tr>\
+                      <tr><td colspan=\"2\"><em>c</em></td></tr>\
+                      </table></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Table,
+                                                         "table".parse().unwrap()),
+                                           Selector::new(SelectorKind::Emphasis,
+                                                         "em".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+
+        let header_cell = |text: &str| {
+            TableCell { header: true, ..TableCell::new(vec![Part::Text(text.to_string())]) }
+        };
+        assert_eq!(document.content,
+                   vec![Part::Table {
+                            header: vec![vec![header_cell("A"), header_cell("B")]],
+                            body: vec![vec![TableCell {
+                                                colspan: 2,
+                                                ..TableCell::new(vec![Part::Emphasis(vec![
+                                                    Part::Text("c".to_string())
+                                                ])])
+                                            }]],
+                        }]);
+    }
+
+    #[test]
+    fn test_table_spans_are_clamped() {
+        let markup = "<html><body><table><tr>\
+                      <td colspan=\"4294967295\" rowspan=\"0\">a</td>\
+                      <td colspan=\"0\" rowspan=\"99999999999\">b</td>\
+                      </tr></table></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Table,
+                                                         "table".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+
+        let spans: Vec<_> = match document.content[0] {
+            Part::Table { ref body, .. } => {
+                body[0].iter().map(|cell| (cell.colspan, cell.rowspan)).collect()
+            }
+            ref other => panic!("Unexpected part {:?}", other),
+        };
+        assert_eq!(spans, vec![(1000, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn test_ordered_list() {
+        let markup = "<html><body><ul><li>a</li></ul><ol start=\"3\"><li>b</li></ol>\
+                      <div class=\"steps\"><p>c</p></div><ol start=\"-2\"><li>d</li></ol>\
+                      </body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::List,
+                                                         "ul, ol".parse().unwrap()),
+                                           Selector::new(SelectorKind::OrderedList,
+                                                         ".steps".parse().unwrap()),
+                                           Selector::new(SelectorKind::ListItem,
+                                                         "li, p".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions::default());
+
+        let item = |text: &str| Part::ListItem(vec![Part::Text(text.to_string())]);
+        assert_eq!(document.content,
+                   vec![Part::List(vec![item("a")]),
+                        Part::OrderedList {
+                            start: 3,
+                            content: vec![item("b")],
+                        },
+                        Part::OrderedList {
+                            start: 1,
+                            content: vec![item("c")],
+                        },
+                        Part::OrderedList {
+                            start: -2,
+                            content: vec![item("d")],
+                        }]);
+    }
+
+    #[test]
+    fn test_srcset_candidates() {
+        assert_eq!(super::srcset_candidates("a.png, b.png 2x,c.png 640w"),
+                   vec![("a.png".to_string(), false, 1.),
+                        ("b.png".to_string(), false, 2.),
+                        ("c.png".to_string(), true, 640.)]);
+        assert_eq!(super::srcset_candidates("//cdn/w_640,h_480/a.jpg 640w, \
+                                             //cdn/w_1280,h_960/a.jpg 1280w"),
+                   vec![("//cdn/w_640,h_480/a.jpg".to_string(), true, 640.),
+                        ("//cdn/w_1280,h_960/a.jpg".to_string(), true, 1280.)]);
+        assert_eq!(super::srcset_candidates("data:image/png;base64,AAAA 1x,small.png, big.png 2x"),
+                   vec![("data:image/png;base64,AAAA".to_string(), false, 1.),
+                        ("small.png".to_string(), false, 1.),
+                        ("big.png".to_string(), false, 2.)]);
+    }
+
+    #[test]
+    fn test_image_sources() {
+        let markup = "<html><body>\
+                      <img src=\"placeholder.gif\" data-src=\"lazy.png\" alt=\"Lazy\">\
+                      <img src=\"small.png\" srcset=\"medium.png 640w, large.png 1280w\">\
+                      <picture><source media=\"(max-width: 10px)\" srcset=\"tiny.png\">\
+                      <source srcset=\"a.png, b.png 2x\"><img src=\"c.png\"></picture>\
+                      </body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Image,
+                                                         "img".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions {
+                                          keep_raw_urls: true,
+                                          ..ExtractorOptions::default()
+                                      });
+
+        let urls: Vec<_> = document.content
+            .iter()
+            .filter_map(|part| {
+                match *part {
+                    Part::Image { ref url, .. } => Some(url.as_str()),
+                    _ => None,
+                }
+            })
+            .collect();
+        assert_eq!(urls, vec!["lazy.png", "large.png", "b.png"]);
+
+        match document.content[0] {
+            Part::Image { ref alt, .. } => assert_eq!(alt, &Some("Lazy".to_string())),
+            ref other => panic!("Unexpected part {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_selector_attribute() {
+        let markup = r#"<html><body><p>
+            <a href="/redirect" data-url="/post">Post</a>
+            <abbr title="HyperText Markup Language">HTML</abbr>
+            <span>Plain</span>
+        </p></body></html>"#;
+        let (document, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Link,
+                                                          "a".parse().unwrap())
+                                                .attribute("data-url".to_string()),
+                                            Selector::new(SelectorKind::Emphasis,
+                                                          "abbr, span".parse().unwrap())
+                                                .attribute("title".to_string())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        let parts: Vec<_> = document.content
+            .into_iter()
+            .filter(|part| part.normalized_text() != "")
+            .collect();
+        assert_eq!(parts,
+                   vec![Part::Link {
+                            url: "http://example.com/post".to_string(),
+                            content: vec![Part::Text("Post".to_string())],
+                        },
+                        Part::Emphasis(vec![Part::Text("HyperText Markup Language".to_string())]),
+                        Part::Emphasis(vec![Part::Text("Plain".to_string())])]);
+        assert_eq!(report.warnings.len(), 1);
+        match report.warnings[0].error {
+            Error::MissingAttribute { ref attribute, .. } => assert_eq!(attribute, "title"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_image_sizes() {
+        let markup = "<html><body>\
+                      <img src=\"a.png\" width=\"640px\" height=\"50%\">\
+                      <img src=\"b.png\" width=\"3\" style=\"width: 20.5em; height:auto\">\
+                      <img src=\"c.png\" width=\"big\" height=\"120\">\
+                      <img src=\"d.png\" width=\"99999999999px\">\
+                      </body></html>";
+        let (document, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Image,
+                                                          "img".parse().unwrap())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        let sizes: Vec<_> = document.content
+            .iter()
+            .filter_map(|part| {
+                match *part {
+                    Part::Image { width, height, ref css_width, ref css_height, .. } => {
+                        Some((width, height, css_width, css_height))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        let dimension = |value: &str, unit: &str| {
+            Some(Dimension {
+                value: value.to_string(),
+                unit: unit.to_string(),
+            })
+        };
+        assert_eq!(sizes,
+                   vec![(Some(640), None, &None, &dimension("50", "%")),
+                        (None, None, &dimension("20.5", "em"), &None),
+                        (None, Some(120), &None, &None),
+                        (None, None, &None, &None)]);
+
+        assert_eq!(report.warnings.len(), 2);
+        match report.warnings[0].error {
+            Error::InvalidDimension { property, ref value, .. } => {
+                assert_eq!(property, "width");
+                assert_eq!(value, "big");
+            }
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+        match report.warnings[1].error {
+            Error::InvalidDimension { ref value, .. } => assert_eq!(value, "99999999999px"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_media() {
+        let markup = "<html><body>\
+                      <iframe src=\"https://www.youtube.com/embed/x\" title=\"Talk\"></iframe>\
+                      <video poster=\"poster.png\"><source src=\"movie.webm\">\
+                      Your browser does not support videos</video>\
+                      <audio><source src=\"\"></audio>\
+                      </body></html>";
+        let (document, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Media,
+                                                          "iframe, video, audio"
+                                                              .parse()
+                                                              .unwrap())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        assert_eq!(document.content,
+                   vec![Part::Embed {
+                            kind: EmbedKind::Frame,
+                            url: "https://www.youtube.com/embed/x".to_string(),
+                            poster: None,
+                            title: Some("Talk".to_string()),
+                        },
+                        Part::Embed {
+                            kind: EmbedKind::Video,
+                            url: "http://example.com/blog/movie.webm".to_string(),
+                            poster: Some("http://example.com/blog/poster.png".to_string()),
+                            title: None,
+                        }]);
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_unsafe_media() {
+        let markup = "<html><body>\
+                      <iframe src=\"javascript:alert(1)\"></iframe>\
+                      <video src=\"movie.webm\" poster=\"data:image/png,x\"></video>\
+                      </body></html>";
+        let (document, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Media,
+                                                          "iframe, video".parse().unwrap())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        assert_eq!(document.content,
+                   vec![Part::Embed {
+                            kind: EmbedKind::Video,
+                            url: "http://example.com/blog/movie.webm".to_string(),
+                            poster: None,
+                            title: None,
+                        }]);
+        assert_eq!(report.warnings.len(), 2);
+        match report.warnings[0].error {
+            Error::UnsafeUrl { ref url, .. } => assert_eq!(url, "javascript:alert(1)"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+        match report.warnings[1].error {
+            Error::UnsafeUrl { ref url, .. } => assert_eq!(url, "data:image/png,x"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let markup = r#"<html lang="en"><head>
+            <meta name="author" content="Ann">
+            <meta name="description" content="About  things">
+            <link rel="canonical" href="/post">
+        </head><body>
+            <p>Hi</p>
+            <span class="author">Bob</span>
+            <a rel="tag" href="/tags/rust">rust</a>
+            <a rel="tag" href="/tags/web">web</a>
+        </body></html>"#;
+        let selectors = vec![Selector::new(SelectorKind::Language, "html".parse().unwrap()),
+                             Selector::new(SelectorKind::Author,
+                                           "meta[name=author], .author".parse().unwrap()),
+                             Selector::new(SelectorKind::Summary,
+                                           "meta[name=description]".parse().unwrap()),
+                             Selector::new(SelectorKind::CanonicalUrl,
+                                           "link[rel=canonical]".parse().unwrap()),
+                             Selector::new(SelectorKind::Tag, "a[rel=tag]".parse().unwrap()),
+                             Selector::new(SelectorKind::Paragraph, "p".parse().unwrap())];
+        let document = extract_markup(selectors, markup, ExtractorOptions::default());
+
+        assert_eq!(document.language, Some("en".to_string()));
+        assert_eq!(document.authors, vec!["Ann".to_string(), "Bob".to_string()]);
+        assert_eq!(document.summary, Some(vec![Part::Text("About  things".to_string())]));
+        assert_eq!(document.canonical_url, Some("http://example.com/post".to_string()));
+        assert_eq!(document.tags, vec!["rust".to_string(), "web".to_string()]);
+        assert!(document.content.contains(&Part::Paragraph(vec![Part::Text("Hi".to_string())])));
+    }
+
+    #[test]
+    fn test_language_without_attribute() {
+        let markup = "<html><body><div class=\"post\"><p>Hi</p></div></body></html>";
+        let (document, report) =
+            extract_markup_with_report(vec![Selector::new(SelectorKind::Language,
+                                                          ".post".parse().unwrap()),
+                                            Selector::new(SelectorKind::Paragraph,
+                                                          "p".parse().unwrap())],
+                                       markup,
+                                       ExtractorOptions::default());
+
+        assert_eq!(document.language, None);
+        assert_eq!(document.content,
+                   vec![Part::Paragraph(vec![Part::Text("Hi".to_string())])]);
+        assert_eq!(report.warnings.len(), 1);
+        match report.warnings[0].error {
+            Error::MissingAttribute { ref attribute, .. } => assert_eq!(attribute, "lang"),
+            ref other => panic!("Unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_figure() {
+        let markup = "<html><body><figure><img src=\"a.png\">\
+                      <figcaption>The <em>caption</em></figcaption></figure></body></html>";
+        let document = extract_markup(vec![Selector::new(SelectorKind::Figure,
+                                                         "figure".parse().unwrap()),
+                                           Selector::new(SelectorKind::Image,
+                                                         "img".parse().unwrap()),
+                                           Selector::new(SelectorKind::Emphasis,
+                                                         "em".parse().unwrap())],
+                                      markup,
+                                      ExtractorOptions {
+                                          keep_raw_urls: true,
+                                          ..ExtractorOptions::default()
+                                      });
+
+        assert_eq!(document.content,
+                   vec![Part::Figure {
+                            content: vec![Part::Image {
+                                              url: "a.png".to_string(),
+                                              width: None,
+                                              height: None,
+                                              legend: None,
+                                              alt: None,
+                                              css_width: None,
+                                              css_height: None,
+                                          }],
+                            caption: Some(vec![Part::Text("The ".to_string()),
+                                               Part::Emphasis(vec![Part::Text("caption"
+                                                                       .to_string())])]),
+                        }]);
+    }
+
     #[test]
     fn selectors_priority() {
         let mut extractor = Extractor::new(ExtractorOptions::default());
@@ -479,7 +1975,7 @@ mod extractor {
                                 Selector::new(SelectorKind::Paragraph, "p".parse().unwrap())],
                            input,
                            ExtractorOptions {
-                               date_format: Some("%B %d, %Y".to_string()),
+                               date_formats: vec![pattern("%B %d, %Y")],
                                root_selector: Some(".post".parse().unwrap()),
                                ..ExtractorOptions::default()
                            });
@@ -488,7 +1984,7 @@ mod extractor {
                                 Selector::new(SelectorKind::Paragraph, "p".parse().unwrap())],
                            expected,
                            ExtractorOptions {
-                               date_format: Some("%Y-%m-%d".to_string()),
+                               date_formats: vec![pattern("%Y-%m-%d")],
                                ..ExtractorOptions::default()
                            });
 
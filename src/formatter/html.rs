@@ -1,8 +1,6 @@
-use ::chrono;
 use std::io;
 use std::error;
-use chrono::TimeZone;
-use part::{Document, Part};
+use part::{PartDate, Document, Part, TableCell, is_safe_url};
 use super::Formatter;
 
 macro_rules! write_el{
@@ -45,30 +43,13 @@ macro_rules! write_el{
 pub struct HtmlFormatter;
 
 impl HtmlFormatter {
+    /// Writes a full HTML page holding the documents. Same as `Formatter::write_full`, kept so
+    /// callers don't have to import the trait.
     pub fn write_full<T: io::Write>(&self,
                                     documents: &[Document],
                                     output: &mut T)
                                     -> Result<(), Box<error::Error>> {
-
-        output.write(br#"<!DOCTYPE html>"#)?;
-        write_el!(output, "html" => {
-            write_el!(output, "head" => {
-                write_el!(output, "meta" {
-                    "charset" = "utf-8"
-                });
-                write_el!(output, "meta" {
-                    "name" = "viewport"
-                    "content" = "device-width"
-                });
-                write_el!(output, "title" => {});
-            });
-            write_el!(output, "body" => {
-                for doc in documents {
-                    self.write_document(doc, output)?;
-                }
-            });
-        });
-        Ok(())
+        Formatter::write_full(self, documents, output)
     }
 
     fn write_escaped<T: io::Write>(text: &str,
@@ -90,15 +71,67 @@ impl HtmlFormatter {
     }
 
     fn write_date<T: io::Write>(&self,
-                                date: &chrono::NaiveDate,
+                                date: &PartDate,
                                 output: &mut T)
                                 -> Result<(), io::Error> {
         write_el!(output, "time" {
-            "datetime" = &chrono::UTC.from_utc_datetime(&date.and_hms(0, 0, 0)).to_rfc3339()
-        } => output.write_all(&date.format("%Y-%m-%d").to_string().as_bytes())?);
+            "datetime" = &date.to_string()
+        } => output.write_all(date.readable().as_bytes())?);
+        Ok(())
+    }
+
+    fn write_header<T: io::Write>(&self, document: &Document, output: &mut T) -> io::Result<()> {
+        if let Some(ref title) = document.title {
+            write_el!(output, "h2" => self.write_parts(title, output)?);
+        }
+        if let Some(ref date) = document.publication_date {
+            write_el!(output, "p" => {
+                output.write_all(b"On ")?;
+                self.write_date(date, output)?
+            });
+        }
+        if !document.authors.is_empty() {
+            write_el!(output, "p" {
+                "class" = "authors"
+            } => {
+                output.write_all(b"By ")?;
+                HtmlFormatter::write_escaped(&document.authors.join(", "), false, output)?
+            });
+        }
+        if let Some(ref summary) = document.summary {
+            write_el!(output, "p" {
+                "class" = "summary"
+            } => self.write_parts(summary, output)?);
+        }
+        if !document.tags.is_empty() {
+            write_el!(output, "ul" {
+                "class" = "tags"
+            } => {
+                for tag in &document.tags {
+                    write_el!(output, "li" => HtmlFormatter::write_escaped(tag, false, output)?);
+                }
+            });
+        }
+        if let Some(ref url) = document.canonical_url {
+            write_el!(output, "p" => {
+                write_el!(output, "a" {
+                    "rel" = "canonical"
+                    "href" = url
+                } => HtmlFormatter::write_escaped(url, false, output)?)
+            });
+        }
         Ok(())
     }
 
+    fn write_article<T: io::Write>(&self, document: &Document, output: &mut T) -> io::Result<()> {
+        if document.title.is_some() || document.publication_date.is_some() ||
+           !document.authors.is_empty() || document.summary.is_some() ||
+           !document.tags.is_empty() || document.canonical_url.is_some() {
+            write_el!(output, "header" => self.write_header(document, output)?);
+        }
+        self.write_parts(&document.content, output)
+    }
+
     fn write_parts<T: io::Write>(&self, children: &[Part], output: &mut T) -> io::Result<()> {
         for child in children {
             self.write_part(child, output)?;
@@ -106,8 +139,68 @@ impl HtmlFormatter {
         Ok(())
     }
 
+    fn write_table_rows<T: io::Write>(&self,
+                                      rows: &[Vec<TableCell>],
+                                      output: &mut T)
+                                      -> io::Result<()> {
+        for row in rows {
+            write_el!(output, "tr" => {
+                for cell in row {
+                    let tag = if cell.header { "th" } else { "td" };
+                    write!(output, "<{}", tag)?;
+                    if cell.colspan != 1 {
+                        write!(output, r#" colspan="{}""#, cell.colspan)?;
+                    }
+                    if cell.rowspan != 1 {
+                        write!(output, r#" rowspan="{}""#, cell.rowspan)?;
+                    }
+                    output.write_all(b">")?;
+                    self.write_parts(&cell.content, output)?;
+                    write!(output, "</{}>\n", tag)?;
+                }
+            });
+        }
+        Ok(())
+    }
+
     fn write_part<T: io::Write>(&self, part: &Part, output: &mut T) -> Result<(), io::Error> {
         match *part {
+            Part::Blockquote { ref cite, ref content, ref attribution } => {
+                let write_content = |output: &mut T| -> io::Result<()> {
+                    self.write_parts(content, output)?;
+                    if let Some(ref attribution) = *attribution {
+                        write_el!(output, "footer" => self.write_parts(attribution, output)?);
+                    }
+                    Ok(())
+                };
+                match *cite {
+                    Some(ref cite) => {
+                        write_el!(output, "blockquote" {
+                            "cite" = cite
+                        } => write_content(output)?)
+                    }
+                    None => write_el!(output, "blockquote" => write_content(output)?),
+                }
+            }
+            Part::CodeBlock { ref language, ref code } => {
+                write_el!(output, "pre" => {
+                    match *language {
+                        Some(ref language) => {
+                            write_el!(output, "code" {
+                                "class" = &format!("language-{}", language)
+                            } => HtmlFormatter::write_escaped(code, false, output)?)
+                        }
+                        None => {
+                            write_el!(output, "code" => {
+                                HtmlFormatter::write_escaped(code, false, output)?
+                            })
+                        }
+                    }
+                })
+            }
+            Part::InlineCode(ref code) => {
+                write_el!(output, "code" => HtmlFormatter::write_escaped(code, false, output)?)
+            }
             Part::Paragraph(ref children) => {
                 write_el!(output, "p" => self.write_parts(children, output)?)
             }
@@ -116,9 +209,46 @@ impl HtmlFormatter {
                 self.write_date(date, output)?;
             }
 
+            Part::Embed { ref url, ref poster, ref title, .. } => {
+                // Embedded content can't be trusted: link to it instead of including it, and only
+                // to web URLs
+                let label = title.as_ref().map_or(&url[..], |title| &title[..]);
+                let poster = poster.as_ref().and_then(|poster| {
+                    if is_safe_url(poster) { Some(poster) } else { None }
+                });
+                write_el!(output, "p" => {
+                    if is_safe_url(url) {
+                        write_el!(output, "a" {
+                            "href" = url
+                        } => {
+                            match poster {
+                                Some(poster) => {
+                                    write_el!(output, "img" {
+                                        "src" = poster
+                                        "alt" = label
+                                    })
+                                }
+                                None => HtmlFormatter::write_escaped(label, false, output)?,
+                            }
+                        })
+                    }
+                    else {
+                        HtmlFormatter::write_escaped(label, false, output)?
+                    }
+                })
+            }
+
             Part::Emphasis(ref children) => {
                 write_el!(output, "em" => self.write_parts(children, output)?)
             }
+            Part::Figure { ref content, ref caption } => {
+                write_el!(output, "figure" => {
+                    self.write_parts(content, output)?;
+                    if let Some(ref caption) = *caption {
+                        write_el!(output, "figcaption" => self.write_parts(caption, output)?);
+                    }
+                })
+            }
             Part::Header1(ref children) => {
                 write_el!(output, "h2" => self.write_parts(children, output)?)
             }
@@ -128,9 +258,10 @@ impl HtmlFormatter {
             Part::Header3(ref children) => {
                 write_el!(output, "h4" => self.write_parts(children, output)?)
             }
-            Part::Image { ref url, .. } => {
+            Part::Image { ref url, ref alt, .. } => {
                 write_el!(output, "img" {
                     "src" = url
+                    "alt" = alt.as_ref().map_or("", |alt| &alt[..])
                 });
             }
             Part::Link { ref url, ref content } => {
@@ -144,6 +275,26 @@ impl HtmlFormatter {
             Part::ListItem(ref children) => {
                 write_el!(output, "li" => self.write_parts(children, output)?)
             }
+            Part::OrderedList { start, ref content } => {
+                if start == 1 {
+                    write_el!(output, "ol" => self.write_parts(content, output)?)
+                }
+                else {
+                    write_el!(output, "ol" {
+                        "start" = &start.to_string()
+                    } => self.write_parts(content, output)?)
+                }
+            }
+            Part::Table { ref header, ref body } => {
+                write_el!(output, "table" => {
+                    if !header.is_empty() {
+                        write_el!(output, "thead" => self.write_table_rows(header, output)?);
+                    }
+                    if !body.is_empty() {
+                        write_el!(output, "tbody" => self.write_table_rows(body, output)?);
+                    }
+                })
+            }
             Part::Text(ref text) => HtmlFormatter::write_escaped(text, false, output)?,
         }
         Ok(())
@@ -151,27 +302,44 @@ impl HtmlFormatter {
 }
 
 impl Formatter for HtmlFormatter {
+    fn write_full<T: io::Write>(&self,
+                                documents: &[Document],
+                                output: &mut T)
+                                -> Result<(), Box<error::Error>> {
+
+        output.write(br#"<!DOCTYPE html>"#)?;
+        write_el!(output, "html" => {
+            write_el!(output, "head" => {
+                write_el!(output, "meta" {
+                    "charset" = "utf-8"
+                });
+                write_el!(output, "meta" {
+                    "name" = "viewport"
+                    "content" = "device-width"
+                });
+                write_el!(output, "title" => {});
+            });
+            write_el!(output, "body" => {
+                for doc in documents {
+                    self.write_document(doc, output)?;
+                }
+            });
+        });
+        Ok(())
+    }
+
     fn write_document<T: io::Write>(&self,
                                     document: &Document,
                                     output: &mut T)
                                     -> Result<(), Box<error::Error>> {
-        write_el!(output, "article" => {
-            if document.title.is_some() || document.publication_date.is_some() {
-                write_el!(output, "header" => {
-                    if let Some(ref title) = document.title {
-                        write_el!(output, "h2" => self.write_parts(title, output)?);
-                    }
-                    if let Some(ref date) = document.publication_date {
-                        write_el!(output, "p" => {
-                            output.write_all(b"On ")?;
-                            self.write_date(date, output)?
-                        });
-                    }
-
-                });
+        match document.language {
+            Some(ref language) => {
+                write_el!(output, "article" {
+                    "lang" = language
+                } => self.write_article(document, output)?)
             }
-            self.write_parts(&document.content, output)?;
-        });
+            None => write_el!(output, "article" => self.write_article(document, output)?),
+        }
         Ok(())
     }
 }
@@ -180,7 +348,8 @@ impl Formatter for HtmlFormatter {
 mod tests {
     use ::chrono;
     use ::formatter::Formatter;
-    use ::part::{Document, Part};
+    use ::part::{PartDate, Document, EmbedKind, Part, TableCell};
+    use chrono::TimeZone;
     use super::HtmlFormatter;
 
     #[test]
@@ -192,7 +361,7 @@ mod tests {
                                                    url: r#"<>""#.to_string(),
                                                    content: vec![Part::Text("link".to_string())],
                                                }])],
-            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7)),
+            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7).into()),
             ..Document::default()
         };
         assert_eq!(&formatter.format(&document).unwrap(),
@@ -200,7 +369,7 @@ mod tests {
             <article>\
                 <header>\
                     <p>\
-                        On <time datetime=\"2000-10-07T00:00:00+00:00\">2000-10-07</time>\n\
+                        On <time datetime=\"2000-10-07\">2000-10-07</time>\n\
                     </p>\n\
                 </header>\n\
                 <p>\
@@ -209,4 +378,177 @@ mod tests {
                 </p>\n\
             </article>\n");
     }
+
+    #[test]
+    fn test_html_datetime() {
+        let formatter = HtmlFormatter {};
+        let date = PartDate::Offset(chrono::FixedOffset::east(2 * 3600)
+            .ymd(2000, 10, 7)
+            .and_hms(8, 30, 0));
+        let document = Document::new(vec![Part::Date(date)]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <time datetime=\"2000-10-07T08:30:00+02:00\">\
+                            2000-10-07 08:30 +0200\
+                        </time>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_metadata() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![])
+            .author("Ann".to_string())
+            .author("Bob".to_string())
+            .summary(vec![Part::Text("About".to_string())])
+            .tag("rust".to_string())
+            .canonical_url("http://a.com/".to_string())
+            .language("en".to_string());
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "\
+            <article lang=\"en\">\
+                <header>\
+                    <p class=\"authors\">By Ann, Bob</p>\n\
+                    <p class=\"summary\">About</p>\n\
+                    <ul class=\"tags\"><li>rust</li>\n</ul>\n\
+                    <p><a rel=\"canonical\" href=\"http://a.com/\">http://a.com/</a>\n</p>\n\
+                </header>\n\
+            </article>\n");
+    }
+
+    #[test]
+    fn test_html_blockquote() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::Blockquote {
+                                              cite: Some("http://foo.com".to_string()),
+                                              content: vec![Part::Text("a".to_string())],
+                                              attribution: Some(vec![Part::Text("b"
+                                                                         .to_string())]),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <blockquote cite=\"http://foo.com\">a<footer>b</footer>\n</blockquote>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_table() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::Table {
+                                              header: vec![vec![TableCell {
+                                                                    header: true,
+                                                                    colspan: 2,
+                                                                    ..TableCell::new(vec![
+                                                                        Part::Text("a".to_string())
+                                                                    ])
+                                                                }]],
+                                              body: vec![vec![TableCell::new(vec![]),
+                                                              TableCell::new(vec![])]],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <table>\
+                            <thead><tr><th colspan=\"2\">a</th>\n</tr>\n</thead>\n\
+                            <tbody><tr><td></td>\n<td></td>\n</tr>\n</tbody>\n\
+                        </table>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_ordered_list() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::OrderedList {
+                                              start: 4,
+                                              content: vec![Part::ListItem(vec![
+                                                  Part::Text("a".to_string())
+                                              ])],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article><ol start=\"4\"><li>a</li>\n</ol>\n</article>\n");
+    }
+
+    #[test]
+    fn test_html_figure() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::Figure {
+                                              content: vec![Part::Image {
+                                                                url: "a.png".to_string(),
+                                                                width: None,
+                                                                height: None,
+                                                                legend: None,
+                                                                alt: Some("A".to_string()),
+                                                                css_width: None,
+                                                                css_height: None,
+                                                            }],
+                                              caption: Some(vec![Part::Text("b".to_string())]),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <figure><img src=\"a.png\" alt=\"A\"/><figcaption>b</figcaption>\n\
+                        </figure>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_embed() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::Embed {
+                                              kind: EmbedKind::Frame,
+                                              url: "http://a.com/<b>".to_string(),
+                                              poster: None,
+                                              title: None,
+                                          },
+                                          Part::Embed {
+                                              kind: EmbedKind::Video,
+                                              url: "a.mp4".to_string(),
+                                              poster: Some("a.png".to_string()),
+                                              title: Some("A".to_string()),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <p><a href=\"http://a.com/<b>\">http://a.com/&lt;b&gt;</a>\n\
+                        </p>\n\
+                        <p><a href=\"a.mp4\"><img src=\"a.png\" alt=\"A\"/></a>\n\
+                        </p>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_unsafe_embed() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::Embed {
+                                              kind: EmbedKind::Frame,
+                                              url: "javascript:alert(1)".to_string(),
+                                              poster: None,
+                                              title: None,
+                                          },
+                                          Part::Embed {
+                                              kind: EmbedKind::Video,
+                                              url: "https://a.com/a.mp4".to_string(),
+                                              poster: Some("javascript:alert(2)".to_string()),
+                                              title: Some("A".to_string()),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <p>javascript:alert(1)\n\
+                        </p>\n\
+                        <p><a href=\"https://a.com/a.mp4\">A</a>\n\
+                        </p>\n\
+                    </article>\n");
+    }
+
+    #[test]
+    fn test_html_code() {
+        let formatter = HtmlFormatter {};
+        let document = Document::new(vec![Part::CodeBlock {
+                                              language: Some("rust".to_string()),
+                                              code: "a < b\n  c".to_string(),
+                                          },
+                                          Part::InlineCode("d".to_string())]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "<article>\
+                        <pre><code class=\"language-rust\">a &lt; b\n  c</code>\n</pre>\n\
+                        <code>d</code>\n\
+                    </article>\n");
+    }
 }
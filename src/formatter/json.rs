@@ -1,5 +1,5 @@
 extern crate serde_json;
-use self::serde_json::ser;
+use self::serde_json::{de, ser};
 use std::io;
 use std::error;
 use part::Document;
@@ -7,7 +7,17 @@ use super::Formatter;
 
 pub struct JsonFormatter;
 
-impl JsonFormatter {}
+impl JsonFormatter {
+    /// Reads a document written by `write_document`.
+    pub fn read_document<R: io::Read>(input: R) -> Result<Document, Box<error::Error>> {
+        Ok(de::from_reader(input)?)
+    }
+
+    /// Reads documents written by `write_full`.
+    pub fn read_documents<R: io::Read>(input: R) -> Result<Vec<Document>, Box<error::Error>> {
+        Ok(de::from_reader(input)?)
+    }
+}
 
 impl Formatter for JsonFormatter {
     fn write_document<T: io::Write>(&self,
@@ -16,6 +26,13 @@ impl Formatter for JsonFormatter {
                                     -> Result<(), Box<error::Error>> {
         Ok(ser::to_writer(output, document)?)
     }
+
+    fn write_full<T: io::Write>(&self,
+                                documents: &[Document],
+                                output: &mut T)
+                                -> Result<(), Box<error::Error>> {
+        Ok(ser::to_writer(output, documents)?)
+    }
 }
 
 #[cfg(test)]
@@ -34,7 +51,7 @@ mod tests {
                                                    url: r#"<>""#.to_string(),
                                                    content: vec![Part::Text("link".to_string())],
                                                }])],
-            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7)),
+            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7).into()),
             ..Document::default()
         };
         assert_eq!(&formatter.format(&document).unwrap(),
@@ -42,6 +59,11 @@ mod tests {
             {\
                 \"title\":null,\
                 \"publication_date\":\"2000-10-07\",\
+                \"authors\":[],\
+                \"summary\":null,\
+                \"tags\":[],\
+                \"canonical_url\":null,\
+                \"language\":null,\
                 \"content\":[\
                     {\
                         \"Paragraph\":[\
@@ -55,4 +77,18 @@ mod tests {
                 ]\
             }");
     }
+
+    #[test]
+    fn test_json_read_back() {
+        let formatter = JsonFormatter {};
+        let documents = vec![Document::new(vec![Part::Text("a".to_string())]),
+                             Document::default()
+                                 .publication_date(chrono::NaiveDate::from_ymd(2000, 10, 7))];
+        let mut output = Vec::new();
+        formatter.write_full(&documents, &mut output).unwrap();
+        assert_eq!(JsonFormatter::read_documents(&output[..]).unwrap(), documents);
+
+        let json = formatter.format(&documents[0]).unwrap();
+        assert_eq!(JsonFormatter::read_document(json.as_bytes()).unwrap(), documents[0]);
+    }
 }
@@ -0,0 +1,502 @@
+use std::cmp;
+use std::io;
+use std::error;
+use part::{Document, Part, TableCell};
+use super::{Formatter, is_blank, quote, table_grid};
+
+#[derive(Default)]
+pub struct MarkdownFormatter;
+
+/// Longest sequence of backticks in a text, used to choose code delimiters.
+fn max_backticks(text: &str) -> usize {
+    let mut max = 0;
+    let mut current = 0;
+    for c in text.chars() {
+        if c == '`' {
+            current += 1;
+            if current > max {
+                max = current;
+            }
+        }
+        else {
+            current = 0;
+        }
+    }
+    max
+}
+
+impl MarkdownFormatter {
+    fn write_escaped(text: &str, output: &mut String) {
+        let mut previous_is_space = output.is_empty() || output.ends_with(' ');
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !previous_is_space {
+                    output.push(' ');
+                }
+                previous_is_space = true;
+                continue;
+            }
+            previous_is_space = false;
+            match c {
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '&' => {
+                    output.push('\\');
+                    output.push(c);
+                }
+                c => output.push(c),
+            }
+        }
+    }
+
+    /// Escapes characters that would turn the beginning of a paragraph into another block
+    /// (list item, setext header underline...).
+    fn escape_block_start(block: String) -> String {
+        let digits = block.chars().take_while(|c| c.is_digit(10)).count();
+        let next = block[digits..].chars().next();
+        match next {
+            Some('.') | Some(')') if digits > 0 => {
+                format!("{}\\{}", &block[..digits], &block[digits..])
+            }
+            Some('-') | Some('+') | Some('=') if digits == 0 => format!("\\{}", block),
+            _ => block,
+        }
+    }
+
+    /// Starts a link label, escaping a preceding `!` that would turn the link into an image.
+    fn open_link(output: &mut String) {
+        if output.ends_with('!') {
+            output.pop();
+            output.push_str("\\!");
+        }
+        output.push('[');
+    }
+
+    fn write_url(url: &str, output: &mut String) {
+        for c in url.chars() {
+            match c {
+                ' ' => output.push_str("%20"),
+                '(' => output.push_str("%28"),
+                ')' => output.push_str("%29"),
+                '<' => output.push_str("%3C"),
+                '>' => output.push_str("%3E"),
+                c => output.push(c),
+            }
+        }
+    }
+
+    fn inline(&self, parts: &[Part]) -> String {
+        let mut result = String::new();
+        self.write_inline_parts(parts, &mut result);
+        result.trim().to_string()
+    }
+
+    fn write_inline_parts(&self, parts: &[Part], output: &mut String) {
+        for part in parts {
+            self.write_inline_part(part, output);
+        }
+    }
+
+    fn write_inline_code(code: &str, output: &mut String) {
+        let delimiter: String = (0..max_backticks(code) + 1).map(|_| '`').collect();
+        let padding = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+        output.push_str(&delimiter);
+        output.push_str(padding);
+        output.push_str(code);
+        output.push_str(padding);
+        output.push_str(&delimiter);
+    }
+
+    fn code_block(language: &Option<String>, code: &str) -> String {
+        let fence: String = (0..cmp::max(3, max_backticks(code) + 1)).map(|_| '`').collect();
+        let mut result = fence.clone();
+        if let Some(ref language) = *language {
+            result.push_str(language);
+        }
+        result.push('\n');
+        result.push_str(code);
+        if !code.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push_str(&fence);
+        result
+    }
+
+    /// Renders a table as a GitHub Flavored Markdown table. The columns and rows covered by a
+    /// spanned cell are left empty to keep the columns aligned.
+    fn table(&self, header: &[Vec<TableCell>], body: &[Vec<TableCell>]) -> String {
+        let mut rows = table_grid(header.iter().chain(body.iter()),
+                                  |cell| self.inline(&cell.content));
+        let columns = rows.first().map(|row| row.len()).unwrap_or(0);
+        if header.is_empty() {
+            rows.insert(0, vec![String::new(); columns]);
+        }
+
+        let mut lines = Vec::new();
+        for (index, row) in rows.into_iter().enumerate() {
+            lines.push(format!("| {} |", row.join(" | ")));
+            if index == 0 {
+                let separator: Vec<&str> = (0..columns).map(|_| "---").collect();
+                lines.push(format!("| {} |", separator.join(" | ")));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn write_inline_part(&self, part: &Part, output: &mut String) {
+        match *part {
+            Part::Table { .. } => MarkdownFormatter::write_escaped(&part.text(), output),
+            Part::CodeBlock { ref code, .. } |
+            Part::InlineCode(ref code) => MarkdownFormatter::write_inline_code(code, output),
+            Part::Date(ref date) => output.push_str(&date.readable()),
+            Part::Emphasis(ref children) => {
+                let content = self.inline(children);
+                if !content.is_empty() {
+                    output.push('*');
+                    output.push_str(&content);
+                    output.push('*');
+                }
+            }
+            Part::Image { ref url, ref legend, ref alt, .. } => {
+                output.push_str("![");
+                if let Some(ref description) = alt.as_ref().or(legend.as_ref()) {
+                    MarkdownFormatter::write_escaped(description.trim(), output);
+                }
+                output.push_str("](");
+                MarkdownFormatter::write_url(url, output);
+                output.push(')');
+            }
+            Part::Embed { ref url, ref poster, ref title, .. } => {
+                let label = title.as_ref().unwrap_or(url);
+                MarkdownFormatter::open_link(output);
+                match *poster {
+                    Some(ref poster) => {
+                        output.push_str("![");
+                        MarkdownFormatter::write_escaped(label.trim(), output);
+                        output.push_str("](");
+                        MarkdownFormatter::write_url(poster, output);
+                        output.push(')');
+                    }
+                    None => MarkdownFormatter::write_escaped(label.trim(), output),
+                }
+                output.push_str("](");
+                MarkdownFormatter::write_url(url, output);
+                output.push(')');
+            }
+            Part::Link { ref url, ref content } => {
+                MarkdownFormatter::open_link(output);
+                output.push_str(&self.inline(content));
+                output.push_str("](");
+                MarkdownFormatter::write_url(url, output);
+                output.push(')');
+            }
+            Part::Text(ref text) => MarkdownFormatter::write_escaped(text, output),
+
+            // Block parts found in an inline context are flattened
+            Part::Blockquote { content: ref children, .. } |
+            Part::Figure { content: ref children, .. } |
+            Part::Header1(ref children) |
+            Part::Header2(ref children) |
+            Part::Header3(ref children) |
+            Part::List(ref children) |
+            Part::ListItem(ref children) |
+            Part::OrderedList { content: ref children, .. } |
+            Part::Paragraph(ref children) => self.write_inline_parts(children, output),
+        }
+    }
+
+    fn list_item(&self, children: &[Part], marker: &str) -> String {
+        super::list_item(&self.blocks(children), marker)
+    }
+
+    fn list(&self, children: &[Part], start: Option<i64>) -> String {
+        let mut items = Vec::new();
+        for (index, child) in children.iter().enumerate() {
+            if is_blank(child) {
+                continue;
+            }
+            let marker = match start {
+                Some(start) => {
+                    let number = start + items.len() as i64;
+                    // Markdown list numbers can't be negative: keep them as text in a bullet
+                    if number < 0 {
+                        format!("- {}\\. ", number)
+                    }
+                    else {
+                        format!("{}. ", number)
+                    }
+                }
+                None => "- ".to_string(),
+            };
+            items.push(match *child {
+                Part::ListItem(ref item_children) => self.list_item(item_children, &marker),
+                _ => self.list_item(&children[index..index + 1], &marker),
+            });
+        }
+        items.join("\n")
+    }
+
+    fn blocks(&self, parts: &[Part]) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+
+        macro_rules! flush_inline {
+            () => {{
+                let paragraph = inline.trim().to_string();
+                if !paragraph.is_empty() {
+                    blocks.push(MarkdownFormatter::escape_block_start(paragraph));
+                }
+                inline.clear();
+            }}
+        }
+
+        for part in parts {
+            match *part {
+                Part::Blockquote { ref content, ref attribution, .. } => {
+                    flush_inline!();
+                    let mut quote_blocks = self.blocks(content);
+                    if let Some(ref attribution) = *attribution {
+                        quote_blocks.push(format!("— {}", self.inline(attribution)));
+                    }
+                    blocks.push(quote(&quote_blocks.join("\n\n"), ">"));
+                }
+                Part::Table { ref header, ref body } => {
+                    flush_inline!();
+                    blocks.push(self.table(header, body));
+                }
+                Part::CodeBlock { ref language, ref code } => {
+                    flush_inline!();
+                    blocks.push(MarkdownFormatter::code_block(language, code));
+                }
+                Part::Figure { ref content, ref caption } => {
+                    flush_inline!();
+                    blocks.extend(self.blocks(content));
+                    if let Some(ref caption) = *caption {
+                        let caption = self.inline(caption);
+                        if !caption.is_empty() {
+                            blocks.push(format!("*{}*", caption));
+                        }
+                    }
+                }
+                Part::Header1(ref children) => {
+                    flush_inline!();
+                    blocks.push(format!("## {}", self.inline(children)));
+                }
+                Part::Header2(ref children) => {
+                    flush_inline!();
+                    blocks.push(format!("### {}", self.inline(children)));
+                }
+                Part::Header3(ref children) => {
+                    flush_inline!();
+                    blocks.push(format!("#### {}", self.inline(children)));
+                }
+                Part::List(ref children) => {
+                    flush_inline!();
+                    let list = self.list(children, None);
+                    if !list.is_empty() {
+                        blocks.push(list);
+                    }
+                }
+                Part::ListItem(ref children) => {
+                    flush_inline!();
+                    blocks.push(self.list_item(children, "- "));
+                }
+                Part::OrderedList { start, ref content } => {
+                    flush_inline!();
+                    let list = self.list(content, Some(start));
+                    if !list.is_empty() {
+                        blocks.push(list);
+                    }
+                }
+                Part::Paragraph(ref children) => {
+                    flush_inline!();
+                    blocks.extend(self.blocks(children));
+                }
+                ref other => self.write_inline_part(other, &mut inline),
+            }
+        }
+        flush_inline!();
+
+        blocks
+    }
+}
+
+impl Formatter for MarkdownFormatter {
+    fn write_document<T: io::Write>(&self,
+                                    document: &Document,
+                                    output: &mut T)
+                                    -> Result<(), Box<error::Error>> {
+        let mut blocks = Vec::new();
+        if let Some(ref title) = document.title {
+            blocks.push(format!("# {}", self.inline(title)));
+        }
+        if let Some(ref date) = document.publication_date {
+            blocks.push(format!("On {}", date.readable()));
+        }
+        blocks.extend(self.blocks(&document.content));
+
+        output.write_all(blocks.join("\n\n").as_bytes())?;
+        output.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::chrono;
+    use ::formatter::Formatter;
+    use ::part::{Document, Part, TableCell};
+    use super::MarkdownFormatter;
+
+    #[test]
+    fn test_markdown_formatter() {
+        let formatter = MarkdownFormatter {};
+        let document = Document {
+            content: vec![Part::Paragraph(vec![Part::Text("Oh *hi*! <>\" &copy;".to_string()),
+                                               Part::Link {
+                                                   url: "/foo (bar)".to_string(),
+                                                   content: vec![Part::Text("link".to_string())],
+                                               }])],
+            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7).into()),
+            ..Document::default()
+        };
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "On 2000-10-07\n\
+                    \n\
+                    Oh \\*hi\\*! \\<\\>\" \\&copy;[link](/foo%20%28bar%29)\n");
+    }
+
+    #[test]
+    fn test_markdown_link_after_exclamation_mark() {
+        let formatter = MarkdownFormatter {};
+        let document = Document::new(vec![Part::Paragraph(vec![Part::Text("Wow!".to_string()),
+                                                               Part::Link {
+                                                                   url: "/a".to_string(),
+                                                                   content: vec![Part::Text("a"
+                                                                       .to_string())],
+                                                               }])]);
+        assert_eq!(&formatter.format(&document).unwrap(), "Wow\\![a](/a)\n");
+    }
+
+    #[test]
+    fn test_markdown_blockquote() {
+        let formatter = MarkdownFormatter {};
+        let document = Document::new(vec![Part::Blockquote {
+                                              cite: None,
+                                              content: vec![Part::Paragraph(vec![
+                                                                Part::Text("a".to_string())]),
+                                                            Part::Paragraph(vec![
+                                                                Part::Text("b".to_string())])],
+                                              attribution: Some(vec![Part::Text("c"
+                                                                         .to_string())]),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "> a\n>\n> b\n>\n> — c\n");
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let formatter = MarkdownFormatter {};
+        let cell = |text: &str| TableCell::new(vec![Part::Text(text.to_string())]);
+        let document = Document::new(vec![Part::Table {
+                                              header: vec![vec![cell("a"), cell("b|c")]],
+                                              body: vec![vec![TableCell {
+                                                                  colspan: 2,
+                                                                  ..cell("d")
+                                                              }],
+                                                         vec![cell("e")]],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "| a | b\\|c |\n\
+                    | --- | --- |\n\
+                    | d |  |\n\
+                    | e |  |\n");
+    }
+
+    #[test]
+    fn test_markdown_table_rowspan() {
+        let formatter = MarkdownFormatter {};
+        let cell = |text: &str| TableCell::new(vec![Part::Text(text.to_string())]);
+        let document = Document::new(vec![Part::Table {
+                                              header: vec![vec![cell("a"), cell("b"), cell("c")]],
+                                              body: vec![vec![TableCell {
+                                                                  rowspan: 2,
+                                                                  ..cell("d")
+                                                              },
+                                                              cell("e"),
+                                                              cell("f")],
+                                                         vec![cell("g"), cell("h")]],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "| a | b | c |\n\
+                    | --- | --- | --- |\n\
+                    | d | e | f |\n\
+                    |  | g | h |\n");
+    }
+
+    #[test]
+    fn test_markdown_ordered_list() {
+        let formatter = MarkdownFormatter {};
+        let item = |text: &str| Part::ListItem(vec![Part::Text(text.to_string())]);
+        let document = Document::new(vec![Part::OrderedList {
+                                              start: 9,
+                                              content: vec![item("a"),
+                                                            Part::Text("\n".to_string()),
+                                                            Part::ListItem(vec![
+                                                                Part::Paragraph(vec![
+                                                                    Part::Text("b".to_string())
+                                                                ]),
+                                                                Part::Paragraph(vec![
+                                                                    Part::Text("c".to_string())
+                                                                ]),
+                                                            ])],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "9. a\n10. b\n\n    c\n");
+
+        let document = Document::new(vec![Part::OrderedList {
+                                              start: -1,
+                                              content: vec![item("a"), item("b")],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(), "- -1\\. a\n0. b\n");
+    }
+
+    #[test]
+    fn test_markdown_code() {
+        let formatter = MarkdownFormatter {};
+        let document = Document::new(vec![Part::Paragraph(vec![Part::Text("Run ".to_string()),
+                                                               Part::InlineCode("a`b"
+                                                                   .to_string())]),
+                                          Part::CodeBlock {
+                                              language: Some("rust".to_string()),
+                                              code: "fn main() {\n    *a = 1;\n}".to_string(),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "Run ``a`b``\n\n```rust\nfn main() {\n    *a = 1;\n}\n```\n");
+    }
+
+    #[test]
+    fn test_markdown_lists() {
+        let formatter = MarkdownFormatter {};
+        let document = Document {
+            title: Some(vec![Part::Text("\n  Title ".to_string())]),
+            content: vec![Part::Text("\n".to_string()),
+                          Part::Header1(vec![Part::Text("Header".to_string())]),
+                          Part::List(vec![Part::Text("\n".to_string()),
+                                          Part::ListItem(vec![Part::Text("a\n b".to_string())]),
+                                          Part::Text("\n".to_string()),
+                                          Part::ListItem(vec![Part::Emphasis(vec![
+                                              Part::Text(" c ".to_string())
+                                          ])])]),
+                          Part::Paragraph(vec![Part::Text("1. not a list".to_string())])],
+            ..Document::default()
+        };
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "# Title\n\
+                    \n\
+                    ## Header\n\
+                    \n\
+                    - a b\n\
+                    - *c*\n\
+                    \n\
+                    1\\. not a list\n");
+    }
+}
@@ -1,16 +1,215 @@
 pub mod html;
 pub mod json;
+pub mod markdown;
+pub mod text;
 
 use std::io;
 use std::error;
-use part::Document;
+use part::{Document, Part, TableCell};
+use self::html::HtmlFormatter;
+use self::json::JsonFormatter;
+use self::markdown::MarkdownFormatter;
+use self::text::TextFormatter;
+
+/// Whether a part is whitespace between the items of a list.
+fn is_blank(part: &Part) -> bool {
+    match *part {
+        Part::Text(ref text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// Prefixes every line of a block with `marker`, as in Markdown blockquotes.
+fn quote(block: &str, marker: &str) -> String {
+    let lines: Vec<String> = block.lines()
+        .map(|line| {
+            if line.is_empty() {
+                marker.to_string()
+            }
+            else {
+                format!("{} {}", marker, line)
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn indent(block: &str, first_prefix: &str, prefix: &str) -> String {
+    if block.is_empty() {
+        return first_prefix.trim_right().to_string();
+    }
+    let mut result = String::new();
+    for (index, line) in block.lines().enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        if index == 0 {
+            result.push_str(first_prefix);
+        }
+        else if !line.is_empty() {
+            result.push_str(prefix);
+        }
+        result.push_str(line);
+    }
+    result
+}
+
+/// Joins the blocks of a list item, the first line starting with `marker` and the others
+/// aligned after it.
+fn list_item(blocks: &[String], marker: &str) -> String {
+    let padding: String = marker.chars().map(|_| ' ').collect();
+    indent(&blocks.join("\n\n"), marker, &padding)
+}
+
+/// Lays the rows of a table out on a grid, where cells spanning several columns or rows are
+/// followed by empty cells. `format` renders the content of a cell. All rows get the same number
+/// of columns.
+fn table_grid<'a, I, F>(rows: I, mut format: F) -> Vec<Vec<String>>
+    where I: Iterator<Item = &'a Vec<TableCell>>,
+          F: FnMut(&TableCell) -> String
+{
+    // Number of following rows still covered by a cell, per column
+    let mut spans: Vec<u32> = Vec::new();
+    let mut grid: Vec<Vec<String>> = Vec::new();
+    for row in rows {
+        let mut cells = Vec::new();
+        let mut row_cells = row.iter();
+        loop {
+            let column = cells.len();
+            if column < spans.len() && spans[column] > 0 {
+                spans[column] -= 1;
+                cells.push(String::new());
+                continue;
+            }
+            let cell = match row_cells.next() {
+                Some(cell) => cell,
+                None => break,
+            };
+            for index in 0..cell.colspan {
+                let column = cells.len();
+                if column >= spans.len() {
+                    spans.push(0);
+                }
+                spans[column] = cell.rowspan.saturating_sub(1);
+                cells.push(if index == 0 { format(cell) } else { String::new() });
+            }
+        }
+        // Cells spanned from above at the end of the row
+        for column in cells.len()..spans.len() {
+            if spans[column] > 0 {
+                spans[column] -= 1;
+            }
+            cells.push(String::new());
+        }
+        grid.push(cells);
+    }
+
+    let columns = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in &mut grid {
+        while row.len() < columns {
+            row.push(String::new());
+        }
+    }
+    grid
+}
 
 pub trait Formatter {
     fn write_document<T: io::Write>(&self, &Document, &mut T) -> Result<(), Box<error::Error>>;
 
+    fn write_full<T: io::Write>(&self,
+                                documents: &[Document],
+                                output: &mut T)
+                                -> Result<(), Box<error::Error>> {
+        for (index, document) in documents.iter().enumerate() {
+            if index > 0 {
+                output.write_all(b"\n")?;
+            }
+            self.write_document(document, output)?;
+        }
+        Ok(())
+    }
+
     fn format(&self, document: &Document) -> Result<String, Box<error::Error>> {
         let mut result = Vec::new();
         self.write_document(document, &mut result)?;
         Ok(String::from_utf8(result).unwrap())
     }
 }
+
+/// Defines `FORMATTER_NAMES`, `AnyFormatter::from_name` and `AnyFormatter::name` from a single
+/// list of names, variants and default formatters.
+macro_rules! formatters {
+    ($($name:tt => $variant:ident($formatter:expr)),*) => {
+        /// Names of the formatters available through `AnyFormatter::from_name`.
+        pub const FORMATTER_NAMES: &'static [&'static str] = &[$($name),*];
+
+        impl AnyFormatter {
+            pub fn from_name(name: &str) -> Option<AnyFormatter> {
+                match name {
+                    $($name => Some(AnyFormatter::$variant($formatter)),)*
+                    _ => None,
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(AnyFormatter::$variant(..) => $name,)*
+                }
+            }
+        }
+    }
+}
+
+formatters! {
+    "html" => Html(HtmlFormatter::default()),
+    "json" => Json(JsonFormatter),
+    "markdown" => Markdown(MarkdownFormatter::default()),
+    "text" => Text(TextFormatter::default())
+}
+
+/// A formatter chosen at runtime, for example from a command line option.
+pub enum AnyFormatter {
+    Html(HtmlFormatter),
+    Json(JsonFormatter),
+    Markdown(MarkdownFormatter),
+    Text(TextFormatter),
+}
+
+impl Formatter for AnyFormatter {
+    fn write_document<T: io::Write>(&self,
+                                    document: &Document,
+                                    output: &mut T)
+                                    -> Result<(), Box<error::Error>> {
+        match *self {
+            AnyFormatter::Html(ref f) => f.write_document(document, output),
+            AnyFormatter::Json(ref f) => f.write_document(document, output),
+            AnyFormatter::Markdown(ref f) => f.write_document(document, output),
+            AnyFormatter::Text(ref f) => f.write_document(document, output),
+        }
+    }
+
+    fn write_full<T: io::Write>(&self,
+                                documents: &[Document],
+                                output: &mut T)
+                                -> Result<(), Box<error::Error>> {
+        match *self {
+            AnyFormatter::Html(ref f) => f.write_full(documents, output),
+            AnyFormatter::Json(ref f) => f.write_full(documents, output),
+            AnyFormatter::Markdown(ref f) => f.write_full(documents, output),
+            AnyFormatter::Text(ref f) => f.write_full(documents, output),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyFormatter, FORMATTER_NAMES};
+
+    #[test]
+    fn test_from_name() {
+        for name in FORMATTER_NAMES {
+            assert_eq!(AnyFormatter::from_name(name).unwrap().name(), *name);
+        }
+        assert!(AnyFormatter::from_name("pdf").is_none());
+    }
+}
@@ -0,0 +1,419 @@
+use std::io;
+use std::error;
+use part::{Document, EmbedKind, Part, TableCell};
+use super::{Formatter, indent, is_blank, quote, table_grid};
+
+pub struct TextFormatter {
+    pub width: usize,
+}
+
+impl Default for TextFormatter {
+    fn default() -> TextFormatter {
+        TextFormatter { width: 72 }
+    }
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for word in text.split_whitespace() {
+        let word_width = word.chars().count();
+        if line_width > 0 && line_width + 1 + word_width > width {
+            lines.push(line);
+            line = String::new();
+            line_width = 0;
+        }
+        if line_width > 0 {
+            line.push(' ');
+            line_width += 1;
+        }
+        line.push_str(word);
+        line_width += word_width;
+    }
+    if line_width > 0 {
+        lines.push(line);
+    }
+    lines
+}
+
+fn underline(text: String, c: char) -> String {
+    let line: String = text.chars().map(|_| c).collect();
+    format!("{}\n{}", text, line)
+}
+
+impl TextFormatter {
+    pub fn new(width: usize) -> TextFormatter {
+        TextFormatter { width: width }
+    }
+
+    fn inline(&self, parts: &[Part], links: &mut Vec<String>) -> String {
+        let mut result = String::new();
+        self.write_inline_parts(parts, links, &mut result);
+        Part::Text(result).normalized_text()
+    }
+
+    fn write_inline_parts(&self, parts: &[Part], links: &mut Vec<String>, output: &mut String) {
+        for part in parts {
+            self.write_inline_part(part, links, output);
+        }
+    }
+
+    fn table(&self,
+             header: &[Vec<TableCell>],
+             body: &[Vec<TableCell>],
+             links: &mut Vec<String>)
+             -> String {
+        let rows = table_grid(header.iter().chain(body.iter()),
+                              |cell| self.inline(&cell.content, links));
+        let mut lines = Vec::new();
+        for (index, row) in rows.into_iter().enumerate() {
+            let line = row.join(" | ").trim_right().to_string();
+            if index + 1 == header.len() {
+                lines.push(underline(line, '-'));
+            }
+            else {
+                lines.push(line);
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn write_inline_part(&self, part: &Part, links: &mut Vec<String>, output: &mut String) {
+        match *part {
+            Part::Table { .. } => output.push_str(&part.text()),
+            Part::CodeBlock { ref code, .. } |
+            Part::InlineCode(ref code) => output.push_str(code),
+            Part::Date(ref date) => output.push_str(&date.readable()),
+            Part::Image { ref url, ref legend, ref alt, .. } => {
+                links.push(url.clone());
+                output.push_str("[image");
+                if let Some(description) = alt.as_ref().or(legend.as_ref()) {
+                    output.push_str(": ");
+                    output.push_str(description);
+                }
+                output.push_str(&format!("][{}]", links.len()));
+            }
+            Part::Embed { kind, ref url, ref title, .. } => {
+                links.push(url.clone());
+                output.push_str(match kind {
+                    EmbedKind::Audio => "[audio",
+                    EmbedKind::Frame => "[embed",
+                    EmbedKind::Video => "[video",
+                });
+                if let Some(ref title) = *title {
+                    output.push_str(": ");
+                    output.push_str(title);
+                }
+                output.push_str(&format!("][{}]", links.len()));
+            }
+            Part::Link { ref url, ref content } => {
+                self.write_inline_parts(content, links, output);
+                links.push(url.clone());
+                output.push_str(&format!("[{}]", links.len()));
+            }
+            Part::Text(ref text) => output.push_str(text),
+
+            Part::Blockquote { content: ref children, .. } |
+            Part::Emphasis(ref children) |
+            Part::Figure { content: ref children, .. } |
+            Part::Header1(ref children) |
+            Part::Header2(ref children) |
+            Part::Header3(ref children) |
+            Part::List(ref children) |
+            Part::ListItem(ref children) |
+            Part::OrderedList { content: ref children, .. } |
+            Part::Paragraph(ref children) => self.write_inline_parts(children, links, output),
+        }
+    }
+
+    fn list_item(&self,
+                 children: &[Part],
+                 marker: &str,
+                 width: usize,
+                 links: &mut Vec<String>)
+                 -> String {
+        let marker_width = marker.chars().count();
+        let blocks = self.blocks(children, width.saturating_sub(marker_width), links);
+        super::list_item(&blocks, marker)
+    }
+
+    fn list(&self,
+            children: &[Part],
+            start: Option<i64>,
+            width: usize,
+            links: &mut Vec<String>)
+            -> String {
+        let mut items = Vec::new();
+        for (index, child) in children.iter().enumerate() {
+            if is_blank(child) {
+                continue;
+            }
+            let marker = match start {
+                Some(start) => format!("{}. ", start + items.len() as i64),
+                None => "* ".to_string(),
+            };
+            items.push(match *child {
+                Part::ListItem(ref item_children) => {
+                    self.list_item(item_children, &marker, width, links)
+                }
+                _ => self.list_item(&children[index..index + 1], &marker, width, links),
+            });
+        }
+        items.join("\n")
+    }
+
+    fn blocks(&self, parts: &[Part], width: usize, links: &mut Vec<String>) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+
+        macro_rules! flush_inline {
+            () => {{
+                let paragraph = Part::Text(inline.clone()).normalized_text();
+                if !paragraph.is_empty() {
+                    blocks.push(wrap(&paragraph, width).join("\n"));
+                }
+                inline.clear();
+            }}
+        }
+
+        for part in parts {
+            match *part {
+                Part::Blockquote { ref content, ref attribution, .. } => {
+                    flush_inline!();
+                    let mut quote_blocks = self.blocks(content, width.saturating_sub(2), links);
+                    if let Some(ref attribution) = *attribution {
+                        quote_blocks.push(format!("-- {}", self.inline(attribution, links)));
+                    }
+                    blocks.push(quote(&quote_blocks.join("\n\n"), "|"));
+                }
+                Part::Table { ref header, ref body } => {
+                    flush_inline!();
+                    blocks.push(self.table(header, body, links));
+                }
+                Part::Figure { ref content, ref caption } => {
+                    flush_inline!();
+                    blocks.extend(self.blocks(content, width, links));
+                    if let Some(ref caption) = *caption {
+                        let caption = self.inline(caption, links);
+                        if !caption.is_empty() {
+                            blocks.push(wrap(&caption, width).join("\n"));
+                        }
+                    }
+                }
+                Part::CodeBlock { ref code, .. } => {
+                    flush_inline!();
+                    blocks.push(indent(code, "    ", "    "));
+                }
+                Part::Header1(ref children) => {
+                    flush_inline!();
+                    blocks.push(underline(self.inline(children, links), '-'));
+                }
+                Part::Header2(ref children) => {
+                    flush_inline!();
+                    blocks.push(underline(self.inline(children, links), '~'));
+                }
+                Part::Header3(ref children) => {
+                    flush_inline!();
+                    blocks.push(self.inline(children, links));
+                }
+                Part::List(ref children) => {
+                    flush_inline!();
+                    let list = self.list(children, None, width, links);
+                    if !list.is_empty() {
+                        blocks.push(list);
+                    }
+                }
+                Part::ListItem(ref children) => {
+                    flush_inline!();
+                    blocks.push(self.list_item(children, "* ", width, links));
+                }
+                Part::OrderedList { start, ref content } => {
+                    flush_inline!();
+                    let list = self.list(content, Some(start), width, links);
+                    if !list.is_empty() {
+                        blocks.push(list);
+                    }
+                }
+                Part::Paragraph(ref children) => {
+                    flush_inline!();
+                    blocks.extend(self.blocks(children, width, links));
+                }
+                ref other => self.write_inline_part(other, links, &mut inline),
+            }
+        }
+        flush_inline!();
+
+        blocks
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn write_document<T: io::Write>(&self,
+                                    document: &Document,
+                                    output: &mut T)
+                                    -> Result<(), Box<error::Error>> {
+        let mut links = Vec::new();
+        let mut blocks = Vec::new();
+        if let Some(ref title) = document.title {
+            blocks.push(underline(self.inline(title, &mut links), '='));
+        }
+        if let Some(ref date) = document.publication_date {
+            blocks.push(format!("On {}", date.readable()));
+        }
+        blocks.extend(self.blocks(&document.content, self.width, &mut links));
+
+        if !links.is_empty() {
+            let references: Vec<String> = links.iter()
+                .enumerate()
+                .map(|(index, url)| format!("[{}] {}", index + 1, url))
+                .collect();
+            blocks.push(references.join("\n"));
+        }
+
+        output.write_all(blocks.join("\n\n").as_bytes())?;
+        output.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::chrono;
+    use ::formatter::Formatter;
+    use ::part::{Document, EmbedKind, Part, TableCell};
+    use super::TextFormatter;
+
+    #[test]
+    fn test_text_formatter() {
+        let formatter = TextFormatter::new(20);
+        let document = Document {
+            title: Some(vec![Part::Text("\n  One year ".to_string())]),
+            content: vec![Part::Paragraph(vec![Part::Text("Oh hi!\n   Here is a ".to_string()),
+                                               Part::Link {
+                                                   url: "http://foo.com".to_string(),
+                                                   content: vec![Part::Text("link".to_string())],
+                                               },
+                                               Part::Text(" for you.".to_string())]),
+                          Part::List(vec![Part::Text("\n".to_string()),
+                                          Part::ListItem(vec![Part::Text("first item which is \
+                                                                          long"
+                                                                  .to_string())]),
+                                          Part::ListItem(vec![Part::Text("second".to_string())])])],
+            publication_date: Some(chrono::NaiveDate::from_ymd(2000, 10, 7).into()),
+            ..Document::default()
+        };
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "One year\n\
+                    ========\n\
+                    \n\
+                    On 2000-10-07\n\
+                    \n\
+                    Oh hi! Here is a\n\
+                    link[1] for you.\n\
+                    \n\
+                    * first item which\n  is long\n\
+                    * second\n\
+                    \n\
+                    [1] http://foo.com\n");
+    }
+
+    #[test]
+    fn test_text_table() {
+        let formatter = TextFormatter::default();
+        let cell = |text: &str| TableCell::new(vec![Part::Text(text.to_string())]);
+        let document = Document::new(vec![Part::Table {
+                                              header: vec![vec![cell("a"), cell("b")]],
+                                              body: vec![vec![TableCell {
+                                                                  colspan: 2,
+                                                                  ..cell("c")
+                                                              }],
+                                                         vec![TableCell {
+                                                                  rowspan: 2,
+                                                                  ..cell("d")
+                                                              },
+                                                              cell("e")],
+                                                         vec![cell("f")]],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "a | b\n-----\nc |\nd | e\n | f\n");
+    }
+
+    #[test]
+    fn test_text_blockquote() {
+        let formatter = TextFormatter::default();
+        let document = Document::new(vec![Part::Blockquote {
+                                              cite: None,
+                                              content: vec![Part::Paragraph(vec![
+                                                                Part::Text("a".to_string())]),
+                                                            Part::Paragraph(vec![
+                                                                Part::Text("b".to_string())])],
+                                              attribution: Some(vec![Part::Text("c"
+                                                                         .to_string())]),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(), "| a\n|\n| b\n|\n| -- c\n");
+    }
+
+    #[test]
+    fn test_text_code() {
+        let formatter = TextFormatter::default();
+        let document = Document::new(vec![Part::Paragraph(vec![Part::Text("Run ".to_string()),
+                                                               Part::InlineCode("a`b"
+                                                                   .to_string())]),
+                                          Part::CodeBlock {
+                                              language: Some("rust".to_string()),
+                                              code: "fn main() {\n    a();\n}".to_string(),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "Run a`b\n\n    fn main() {\n        a();\n    }\n");
+    }
+
+    #[test]
+    fn test_text_ordered_list() {
+        let formatter = TextFormatter::default();
+        let item = |text: &str| Part::ListItem(vec![Part::Text(text.to_string())]);
+        let document = Document::new(vec![Part::OrderedList {
+                                              start: 9,
+                                              content: vec![item("a"),
+                                                            Part::Text("\n".to_string()),
+                                                            item("b")],
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(), "9. a\n10. b\n");
+    }
+
+    #[test]
+    fn test_text_embed() {
+        let formatter = TextFormatter::default();
+        let document = Document::new(vec![Part::Paragraph(vec![Part::Text("Watch "
+                                                                   .to_string()),
+                                                               Part::Embed {
+                                                                   kind: EmbedKind::Video,
+                                                                   url: "http://a.com/v"
+                                                                       .to_string(),
+                                                                   poster: None,
+                                                                   title: Some("Demo"
+                                                                       .to_string()),
+                                                               }])]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "Watch [video: Demo][1]\n\n[1] http://a.com/v\n");
+    }
+
+    #[test]
+    fn test_text_figure() {
+        let formatter = TextFormatter::default();
+        let document = Document::new(vec![Part::Figure {
+                                              content: vec![Part::Image {
+                                                                url: "a.png".to_string(),
+                                                                width: None,
+                                                                height: None,
+                                                                legend: None,
+                                                                alt: Some("A".to_string()),
+                                                                css_width: None,
+                                                                css_height: None,
+                                                            }],
+                                              caption: Some(vec![Part::Text("b".to_string())]),
+                                          }]);
+        assert_eq!(&formatter.format(&document).unwrap(),
+                   "[image: A][1]\n\nb\n\n[1] a.png\n");
+    }
+}
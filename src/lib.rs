@@ -6,24 +6,38 @@ extern crate serde;
 
 #[macro_use]
 extern crate mime;
+#[macro_use]
+extern crate lazy_static;
 extern crate hyper;
 extern crate regex;
 extern crate kuchiki;
 extern crate html5ever;
 extern crate chrono;
+extern crate url;
 
 #[cfg(test)]mod mock;
+mod error;
 mod rule;
 mod rules;
 mod matcher;
 mod website;
 mod extractor;
 mod date;
+mod metadata;
+mod readability;
 mod part;
+mod report;
 mod formatter;
 
+pub use error::{Error, ErrorContext};
+pub use extractor::SelectorKind;
 pub use website::Website;
 pub use rules::Rules;
-pub use formatter::Formatter;
+pub use readability::auto_extract;
+pub use part::{PartDate, Dimension, Document, EmbedKind, Part, TableCell};
+pub use report::{Report, Warning};
+pub use formatter::{Formatter, AnyFormatter, FORMATTER_NAMES};
 pub use formatter::html::HtmlFormatter;
 pub use formatter::json::JsonFormatter;
+pub use formatter::markdown::MarkdownFormatter;
+pub use formatter::text::TextFormatter;
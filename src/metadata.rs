@@ -0,0 +1,226 @@
+extern crate serde_json;
+use ::kuchiki;
+use self::serde_json::Value;
+use date::parse_date;
+use extractor::SelectorKind;
+use part::{PartDate, Document, Part};
+
+/// schema.org types describing an article.
+const ARTICLE_TYPES: &'static [&'static str] = &["Article",
+                                                 "BlogPosting",
+                                                 "NewsArticle",
+                                                 "Report",
+                                                 "ScholarlyArticle",
+                                                 "TechArticle"];
+
+/// Non-empty contents of the `<meta>` elements whose `property` or `name` is one of the given
+/// names, in the order of the names.
+fn meta_contents(root: &kuchiki::NodeRef, names: &[&str]) -> Vec<String> {
+    let metas: Vec<_> = match root.select("meta[content]") {
+        Ok(metas) => metas.collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut contents = Vec::new();
+    for name in names {
+        for meta in &metas {
+            let attributes = meta.attributes.borrow();
+            let matches = attributes.get("property")
+                .or_else(|| attributes.get("name"))
+                .map_or(false, |meta_name| meta_name.trim().to_lowercase() == *name);
+            if let Some(content) = attributes.get("content") {
+                if matches && !content.trim().is_empty() {
+                    contents.push(content.trim().to_string());
+                }
+            }
+        }
+    }
+    contents
+}
+
+fn is_article_type(value: &Value) -> bool {
+    value.as_str().map_or(false, |name| ARTICLE_TYPES.iter().any(|article| *article == name))
+}
+
+fn collect_articles(value: Value, articles: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_articles(value, articles);
+            }
+        }
+        Value::Object(object) => {
+            let is_article = match object.get("@type") {
+                Some(&Value::Array(ref types)) => types.iter().any(is_article_type),
+                Some(value) => is_article_type(value),
+                None => false,
+            };
+            if is_article {
+                articles.push(Value::Object(object));
+            }
+            else if let Some(graph) = object.get("@graph") {
+                collect_articles(graph.clone(), articles);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// schema.org articles described by the JSON-LD scripts of the page.
+fn json_ld_articles(root: &kuchiki::NodeRef) -> Vec<Value> {
+    let mut articles = Vec::new();
+    if let Ok(scripts) = root.select("script[type='application/ld+json']") {
+        for script in scripts {
+            if let Ok(value) = serde_json::from_str::<Value>(&script.text_contents()) {
+                collect_articles(value, &mut articles);
+            }
+        }
+    }
+    articles
+}
+
+/// Names of schema.org persons, which may be plain strings, objects or arrays of those.
+fn json_ld_names(value: &Value, names: &mut Vec<String>) {
+    match *value {
+        Value::String(ref name) => names.push(name.trim().to_string()),
+        Value::Array(ref values) => {
+            for value in values {
+                json_ld_names(value, names);
+            }
+        }
+        Value::Object(ref object) => {
+            if let Some(name) = object.get("name") {
+                json_ld_names(name, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Completes the title, publication date and authors missing from a document with the metadata
+/// published by the page: OpenGraph and similar `<meta>` tags, and schema.org `Article` JSON-LD.
+/// The fields of the `selected` kinds are left alone, the rule having selectors for them.
+pub fn complete_document(document: &mut Document,
+                         root: &kuchiki::NodeRef,
+                         selected: &[SelectorKind]) {
+    let articles = json_ld_articles(root);
+    let article_strings = |key: &str| -> Vec<String> {
+        articles.iter()
+            .filter_map(|article| article.find(key).and_then(|value| value.as_str()))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+
+    if document.title.is_none() && !selected.contains(&SelectorKind::Title) {
+        let mut titles = meta_contents(root, &["og:title", "twitter:title"]);
+        titles.extend(article_strings("headline"));
+        document.title = titles.into_iter().next().map(|title| vec![Part::Text(title)]);
+    }
+
+    if document.publication_date.is_none() &&
+       !selected.contains(&SelectorKind::PublicationDate) {
+        let mut dates = meta_contents(root, &["article:published_time", "og:published_time"]);
+        dates.extend(article_strings("datePublished"));
+        document.publication_date = dates.iter()
+            .filter_map(|date| {
+                date.parse::<PartDate>().ok().or_else(|| parse_date("%Y-%m-%d", date).ok())
+            })
+            .next();
+    }
+
+    if document.authors.is_empty() && !selected.contains(&SelectorKind::Author) {
+        // article:author is often the URL of a profile page
+        let mut authors: Vec<String> = meta_contents(root, &["author", "article:author"])
+            .into_iter()
+            .filter(|author| !author.starts_with("http://") && !author.starts_with("https://"))
+            .collect();
+        for article in &articles {
+            if let Some(author) = article.find("author") {
+                json_ld_names(author, &mut authors);
+            }
+        }
+        for author in authors {
+            if !author.is_empty() && !document.authors.contains(&author) {
+                document.authors.push(author);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::chrono;
+    use ::kuchiki;
+    use kuchiki::traits::TendrilSink;
+    use chrono::TimeZone;
+    use extractor::SelectorKind;
+    use part::{PartDate, Document, Part};
+    use super::complete_document;
+
+    fn complete(markup: &str, document: &mut Document) {
+        complete_document(document, &kuchiki::parse_html().one(markup), &[]);
+    }
+
+    #[test]
+    fn test_meta() {
+        let markup = r#"<html><head>
+            <meta property="og:title" content="One year">
+            <meta property="article:published_time" content="2016-05-16T10:00:00+02:00">
+            <meta name="author" content="Ann">
+            <meta property="article:author" content="https://example.com/ann">
+        </head></html>"#;
+        let mut document = Document::default();
+        complete(markup, &mut document);
+        assert_eq!(document.title, Some(vec![Part::Text("One year".to_string())]));
+        assert_eq!(document.publication_date,
+                   Some(PartDate::Offset(chrono::FixedOffset::east(2 * 3600)
+                       .ymd(2016, 5, 16)
+                       .and_hms(10, 0, 0))));
+        assert_eq!(document.authors, vec!["Ann".to_string()]);
+    }
+
+    #[test]
+    fn test_json_ld() {
+        let markup = r#"<html><head><script type="application/ld+json">{
+            "@context": "http://schema.org",
+            "@graph": [
+                {"@type": "WebSite", "name": "Blog"},
+                {
+                    "@type": "BlogPosting",
+                    "headline": "One year",
+                    "datePublished": "2016-05-16",
+                    "author": [{"@type": "Person", "name": "Ann"}, "Bob"]
+                }
+            ]
+        }</script></head></html>"#;
+        let mut document = Document::default();
+        complete(markup, &mut document);
+        assert_eq!(document.title, Some(vec![Part::Text("One year".to_string())]));
+        assert_eq!(document.publication_date,
+                   Some(PartDate::Date(chrono::NaiveDate::from_ymd(2016, 5, 16))));
+        assert_eq!(document.authors, vec!["Ann".to_string(), "Bob".to_string()]);
+    }
+
+    #[test]
+    fn test_keeps_extracted_metadata() {
+        let markup = r#"<html><head><meta property="og:title" content="Other"></head></html>"#;
+        let mut document = Document::default().title(vec![Part::Text("Title".to_string())]);
+        complete(markup, &mut document);
+        assert_eq!(document.title, Some(vec![Part::Text("Title".to_string())]));
+    }
+
+    #[test]
+    fn test_skips_selected_fields() {
+        let markup = r#"<html><head>
+            <meta property="og:title" content="Other">
+            <meta name="author" content="Ann">
+        </head></html>"#;
+        let mut document = Document::default();
+        complete_document(&mut document,
+                          &kuchiki::parse_html().one(markup),
+                          &[SelectorKind::Title]);
+        assert_eq!(document.title, None);
+        assert_eq!(document.authors, vec!["Ann".to_string()]);
+    }
+}
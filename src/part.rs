@@ -1,56 +1,149 @@
 use ::chrono;
 use ::regex;
+use ::serde;
+use serde::de;
+use ::url::{self, Url};
+use std::fmt;
+use std::str;
 
+/// Whether a URL can be linked to without running content: `http` and `https` URLs, and
+/// relative URLs.
+pub fn is_safe_url(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+        Err(url::ParseError::RelativeUrlWithoutBase) => true,
+        Err(_) => false,
+    }
+}
+
+/// A node of an extracted document.
+///
+/// Parts are serialized as externally tagged values (`{"Paragraph":[...]}`), the variant name
+/// being the tag. This representation is stable: new variants and fields may be added, but
+/// existing ones keep their name and shape.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Part {
-    Date(chrono::NaiveDate),
+    /// A quoted passage, with the URL of its source and its attribution if any.
+    Blockquote {
+        cite: Option<String>,
+        content: Vec<Part>,
+        attribution: Option<Vec<Part>>,
+    },
+    /// Preformatted code, with whitespaces preserved.
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+    Date(PartDate),
+    /// Embedded media, like a video or an audio file.
+    Embed {
+        kind: EmbedKind,
+        url: String,
+        /// URL of an image shown before the media is played.
+        poster: Option<String>,
+        title: Option<String>,
+    },
     Emphasis(Vec<Part>),
+    /// Self-contained content, usually an image, with its caption.
+    Figure {
+        content: Vec<Part>,
+        caption: Option<Vec<Part>>,
+    },
     Header1(Vec<Part>),
     Header2(Vec<Part>),
     Header3(Vec<Part>),
     Image {
         url: String,
+        /// Width in pixels.
         width: Option<u32>,
+        /// Height in pixels.
         height: Option<u32>,
         legend: Option<String>,
+        /// Alternative text of the image.
+        #[serde(default)]
+        alt: Option<String>,
+        /// Width expressed in a unit other than pixels (ex: `50%`).
+        #[serde(default)]
+        css_width: Option<Dimension>,
+        /// Height expressed in a unit other than pixels (ex: `10em`).
+        #[serde(default)]
+        css_height: Option<Dimension>,
     },
+    /// Code inside a text, with whitespaces preserved.
+    InlineCode(String),
     Link {
         url: String,
+        #[serde(default)]
         content: Vec<Part>,
     },
     List(Vec<Part>),
     ListItem(Vec<Part>),
+    /// A numbered list, the first item being numbered `start`, which may be zero or negative.
+    OrderedList {
+        start: i64,
+        content: Vec<Part>,
+    },
     Paragraph(Vec<Part>),
+    /// A table, as header and body rows of cells.
+    Table {
+        header: Vec<Vec<TableCell>>,
+        body: Vec<Vec<TableCell>>,
+    },
 
     // Special parts
+    /// Raw text, with the whitespaces of the source document.
     Text(String),
 }
 
 impl Part {
     pub fn children(&self) -> &[Part] {
         match *self {
+            Part::Blockquote { content: ref children, .. } |
             Part::Emphasis(ref children) |
+            Part::Figure { content: ref children, .. } |
             Part::Header1(ref children) |
             Part::Header2(ref children) |
             Part::Header3(ref children) |
             Part::Link { content: ref children, .. } |
             Part::List(ref children) |
             Part::ListItem(ref children) |
+            Part::OrderedList { content: ref children, .. } |
             Part::Paragraph(ref children) => children,
 
+            Part::CodeBlock { .. } |
             Part::Date(..) |
+            Part::Embed { .. } |
             Part::Image { .. } |
+            Part::InlineCode(..) |
+            Part::Table { .. } |
             Part::Text(..) => &[],
         }
     }
 
+    /// Concatenated text of the part and its descendants.
     pub fn text(&self) -> String {
         match *self {
-            Part::Text(ref text) => text.to_string(),
+            Part::Text(ref text) |
+            Part::CodeBlock { code: ref text, .. } |
+            Part::InlineCode(ref text) => text.to_string(),
+            Part::Table { ref header, ref body } => {
+                let rows: Vec<String> = header.iter()
+                    .chain(body.iter())
+                    .map(|row| {
+                        let cells: Vec<String> = row.iter()
+                            .map(|cell| cell.content.iter().map(|c| c.text()).collect())
+                            .collect();
+                        cells.join(" ")
+                    })
+                    .collect();
+                rows.join("\n")
+            }
             ref other => other.children().iter().map(|c| c.text()).collect(),
         }
     }
 
+    /// Same as `text`, with leading and trailing whitespaces removed and other whitespace
+    /// sequences collapsed into a single space.
     pub fn normalized_text(&self) -> String {
         let re = regex::Regex::new(r"(^\s+|\s+$)|\s+").unwrap();
         re.replace_all(&self.text(), |captures: &regex::Captures| {
@@ -59,29 +152,310 @@ impl Part {
     }
 }
 
+/// A date, with its time of day and timezone offset when known.
+///
+/// Serialized as an ISO 8601 string (ex: `2016-05-16`, `2016-05-16T10:30:00`,
+/// `2016-05-16T10:30:00+02:00`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartDate {
+    Date(chrono::NaiveDate),
+    /// A date and time in an unknown timezone.
+    Local(chrono::NaiveDateTime),
+    /// A date and time with a timezone offset.
+    Offset(chrono::DateTime<chrono::FixedOffset>),
+}
+
+impl PartDate {
+    /// Day of the date, in its own timezone.
+    pub fn date(&self) -> chrono::NaiveDate {
+        match *self {
+            PartDate::Date(date) => date,
+            PartDate::Local(datetime) => datetime.date(),
+            PartDate::Offset(datetime) => datetime.date().naive_local(),
+        }
+    }
+
+    /// Human readable representation (ex: `2016-05-16 10:30 +0200`).
+    pub fn readable(&self) -> String {
+        match *self {
+            PartDate::Date(date) => date.format("%Y-%m-%d").to_string(),
+            PartDate::Local(datetime) => datetime.format("%Y-%m-%d %H:%M").to_string(),
+            PartDate::Offset(datetime) => datetime.format("%Y-%m-%d %H:%M %z").to_string(),
+        }
+    }
+}
+
+impl From<chrono::NaiveDate> for PartDate {
+    fn from(date: chrono::NaiveDate) -> PartDate {
+        PartDate::Date(date)
+    }
+}
+
+impl fmt::Display for PartDate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PartDate::Date(date) => write!(formatter, "{}", date.format("%Y-%m-%d")),
+            PartDate::Local(datetime) => {
+                write!(formatter, "{}", datetime.format("%Y-%m-%dT%H:%M:%S%.f"))
+            }
+            PartDate::Offset(datetime) => write!(formatter, "{}", datetime.to_rfc3339()),
+        }
+    }
+}
+
+impl str::FromStr for PartDate {
+    type Err = chrono::ParseError;
+
+    /// Parses the ISO 8601 representation produced by `Display`. Seconds and their fraction are
+    /// optional.
+    fn from_str(s: &str) -> Result<PartDate, chrono::ParseError> {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .or_else(|_| chrono::DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M%z"))
+            .map(PartDate::Offset)
+            .or_else(|_| {
+                chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+                    .or_else(|_| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+                    .map(PartDate::Local)
+            })
+            .or_else(|_| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map(PartDate::Date))
+    }
+}
+
+impl serde::Serialize for PartDate {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl serde::Deserialize for PartDate {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde::Deserializer
+    {
+        deserializer.deserialize_str(PartDateVisitor)
+    }
+}
+
+struct PartDateVisitor;
+
+impl de::Visitor for PartDateVisitor {
+    type Value = PartDate;
+
+    fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        value.parse().map_err(|_| E::custom(format!("Invalid date {:?}", value)))
+    }
+}
+
+/// Kind of a `Part::Embed`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbedKind {
+    Audio,
+    /// A page embedded with an `<iframe>`, like a video player.
+    Frame,
+    Video,
+}
+
+/// A length of a `Part::Image` in a unit other than pixels, as found in the document.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dimension {
+    /// Number as written in the document (ex: `33.3`).
+    pub value: String,
+    /// Lowercased CSS unit (ex: `%`, `em`, `vw`).
+    pub unit: String,
+}
+
+/// A cell of a `Part::Table`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableCell {
+    /// Whether the cell is a header cell (`<th>`).
+    #[serde(default)]
+    pub header: bool,
+    #[serde(default = "default_span")]
+    pub colspan: u32,
+    #[serde(default = "default_span")]
+    pub rowspan: u32,
+    pub content: Vec<Part>,
+}
+
+fn default_span() -> u32 {
+    1
+}
+
+impl TableCell {
+    pub fn new(content: Vec<Part>) -> TableCell {
+        TableCell {
+            header: false,
+            colspan: 1,
+            rowspan: 1,
+            content: content,
+        }
+    }
+}
+
+/// An extracted document.
+///
+/// Missing fields are set to their default value when deserializing, so documents serialized by
+/// older versions can still be read.
 #[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Document {
+    #[serde(default)]
     pub title: Option<Vec<Part>>,
-    pub publication_date: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    pub publication_date: Option<PartDate>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Short description of the document.
+    #[serde(default)]
+    pub summary: Option<Vec<Part>>,
+    /// Tags and categories of the document.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    /// Language of the document, usually as a BCP 47 tag (ex: `en-US`).
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
     pub content: Vec<Part>,
 }
 
+impl Document {
+    /// Creates a document with the given content and no metadata.
+    ///
+    /// ```
+    /// # extern crate exegesis;
+    /// # use exegesis::{Document, Part};
+    /// # fn main() {
+    /// let document = Document::new(vec![Part::Paragraph(vec![Part::Text("Hi!".to_string())])])
+    ///     .title(vec![Part::Text("Greetings".to_string())]);
+    /// assert!(document.title.is_some());
+    /// # }
+    /// ```
+    pub fn new(content: Vec<Part>) -> Document {
+        Document { content: content, ..Document::default() }
+    }
+
+    /// Sets the title of the document.
+    pub fn title(mut self, title: Vec<Part>) -> Document {
+        self.title = Some(title);
+        self
+    }
+
+    /// Sets the publication date of the document.
+    pub fn publication_date<T: Into<PartDate>>(mut self, date: T) -> Document {
+        self.publication_date = Some(date.into());
+        self
+    }
+
+    /// Adds an author to the document.
+    pub fn author(mut self, author: String) -> Document {
+        self.authors.push(author);
+        self
+    }
+
+    /// Sets the summary of the document.
+    pub fn summary(mut self, summary: Vec<Part>) -> Document {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Adds a tag to the document.
+    pub fn tag(mut self, tag: String) -> Document {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Sets the canonical URL of the document.
+    pub fn canonical_url(mut self, url: String) -> Document {
+        self.canonical_url = Some(url);
+        self
+    }
+
+    /// Sets the language of the document.
+    pub fn language(mut self, language: String) -> Document {
+        self.language = Some(language);
+        self
+    }
+}
+
 #[cfg(test)]
 mod parts {
     extern crate serde_json;
     use ::chrono;
-    use super::Part;
+    use chrono::TimeZone;
+    use super::{PartDate, Document, Part};
 
     #[test]
     fn test_serialization() {
-        assert_eq!(serde_json::to_string(&Part::Date(chrono::NaiveDate::from_ymd(2000, 10, 8)))
-                       .unwrap(),
+        let date = PartDate::Date(chrono::NaiveDate::from_ymd(2000, 10, 8));
+        assert_eq!(serde_json::to_string(&Part::Date(date)).unwrap(),
                    r#"{"Date":"2000-10-08"}"#);
     }
 
     #[test]
     fn test_deserialization() {
         assert_eq!(serde_json::from_str::<Part>(r#"{"Date":"2000-10-08"}"#).unwrap(),
-                   Part::Date(chrono::NaiveDate::from_ymd(2000, 10, 8)));
+                   Part::Date(PartDate::Date(chrono::NaiveDate::from_ymd(2000, 10, 8))));
+    }
+
+    #[test]
+    fn test_datetime_round_trip() {
+        let dates = vec![PartDate::Local(chrono::NaiveDate::from_ymd(2000, 10, 8)
+                             .and_hms(10, 30, 0)),
+                         PartDate::Offset(chrono::FixedOffset::east(2 * 3600)
+                             .ymd(2000, 10, 8)
+                             .and_hms(10, 30, 0))];
+        for date in dates {
+            let json = serde_json::to_string(&Part::Date(date.clone())).unwrap();
+            assert_eq!(serde_json::from_str::<Part>(&json).unwrap(), Part::Date(date));
+        }
+        assert_eq!(PartDate::Offset(chrono::FixedOffset::east(2 * 3600)
+                           .ymd(2000, 10, 8)
+                           .and_hms(10, 30, 0))
+                       .to_string(),
+                   "2000-10-08T10:30:00+02:00");
+    }
+
+    #[test]
+    fn test_datetime_parsing() {
+        let date = chrono::NaiveDate::from_ymd(2000, 10, 8);
+        let offset = chrono::FixedOffset::east(2 * 3600);
+        assert_eq!("2000-10-08T10:30".parse::<PartDate>().unwrap(),
+                   PartDate::Local(date.and_hms(10, 30, 0)));
+        assert_eq!("2000-10-08T10:30:15.250".parse::<PartDate>().unwrap(),
+                   PartDate::Local(date.and_hms_milli(10, 30, 15, 250)));
+        assert_eq!("2000-10-08T10:30+02:00".parse::<PartDate>().unwrap(),
+                   PartDate::Offset(offset.ymd(2000, 10, 8).and_hms(10, 30, 0)));
+        assert_eq!("2000-10-08T10:30:15.250+02:00".parse::<PartDate>().unwrap(),
+                   PartDate::Offset(offset.ymd(2000, 10, 8).and_hms_milli(10, 30, 15, 250)));
+
+        let dates = vec![PartDate::Local(date.and_hms_milli(10, 30, 15, 250)),
+                         PartDate::Offset(offset.ymd(2000, 10, 8).and_hms_milli(10, 30, 15, 250))];
+        for date in dates {
+            assert_eq!(date.to_string().parse::<PartDate>().unwrap(), date);
+        }
+    }
+
+    #[test]
+    fn test_document_deserialization_defaults() {
+        assert_eq!(serde_json::from_str::<Document>(r#"{"content":[{"Text":"a"}]}"#).unwrap(),
+                   Document::new(vec![Part::Text("a".to_string())]));
+        assert_eq!(serde_json::from_str::<Document>("{}").unwrap(),
+                   Document::default());
+    }
+
+    #[test]
+    fn test_document_round_trip() {
+        let document = Document::new(vec![Part::Link {
+                                              url: "http://foo.com".to_string(),
+                                              content: vec![Part::Text("foo".to_string())],
+                                          }])
+            .title(vec![Part::Text("Title".to_string())])
+            .publication_date(chrono::NaiveDate::from_ymd(2000, 10, 8));
+        let json = serde_json::to_string(&document).unwrap();
+        assert_eq!(serde_json::from_str::<Document>(&json).unwrap(), document);
     }
 }
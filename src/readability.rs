@@ -0,0 +1,281 @@
+use ::kuchiki;
+use ::regex::Regex;
+use ::url::Url;
+use error::Error;
+use extractor::{Extractor, ExtractorOptions, Selector, SelectorKind, is_any_element, is_element};
+use part::{Document, Part};
+use report::Report;
+use website::Website;
+
+/// Selectors used to extract the content block found by the heuristics.
+const SELECTORS: &'static [(SelectorKind, &'static str)] =
+    &[(SelectorKind::Blockquote, "blockquote"),
+      (SelectorKind::CodeBlock, "pre"),
+      (SelectorKind::Emphasis, "em, i, strong, b"),
+      (SelectorKind::Figure, "figure"),
+      (SelectorKind::Header1, "h1, h2"),
+      (SelectorKind::Header2, "h3"),
+      (SelectorKind::Header3, "h4, h5, h6"),
+      (SelectorKind::Image, "img, picture"),
+      (SelectorKind::InlineCode, "code"),
+      (SelectorKind::Link, "a[href]"),
+      (SelectorKind::List, "ul"),
+      (SelectorKind::ListItem, "li"),
+      (SelectorKind::Media, "video, audio, iframe"),
+      (SelectorKind::OrderedList, "ol"),
+      (SelectorKind::Paragraph, "p"),
+      (SelectorKind::Table, "table")];
+
+/// Elements never part of the content.
+const EXCLUDED: &'static str = "script, style, noscript, nav, aside, form, footer, button";
+
+/// Class names and ids of elements unlikely to hold the content.
+const UNLIKELY: &'static str = "(?i)ad-break|agegate|banner|breadcrumb|combx|comment|community|\
+                                 cookie|disqus|extra|footer|menu|modal|nav|pager|pagination|popup|\
+                                 related|remark|share|shoutbox|sidebar|social|sponsor";
+/// Class names and ids overriding `UNLIKELY`.
+const MAYBE: &'static str = "(?i)and|article|body|column|main|shadow";
+const POSITIVE: &'static str = "(?i)article|blog|body|content|entry|main|page|post|story|text";
+const NEGATIVE: &'static str = "(?i)comment|contact|foot|footnote|hidden|masthead|meta|outbrain|\
+                                 promo|related|scroll|share|shoutbox|sidebar|skyscraper|sponsor|\
+                                 shopping|tags|tool|widget";
+
+/// Minimal length of a paragraph to be taken into account.
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+fn class_and_id(node: &kuchiki::NodeRef) -> String {
+    match node.as_element() {
+        Some(element) => {
+            let attributes = element.attributes.borrow();
+            let names = format!("{} {}",
+                                attributes.get("class").unwrap_or(""),
+                                attributes.get("id").unwrap_or(""));
+            names
+        }
+        None => String::new(),
+    }
+}
+
+fn text_length(node: &kuchiki::NodeRef) -> usize {
+    node.text_contents().trim().chars().count()
+}
+
+/// Proportion of the text of a node inside links.
+fn link_density(node: &kuchiki::NodeRef) -> f64 {
+    let length = text_length(node);
+    if length == 0 {
+        return 0.;
+    }
+    let link_length = node.select("a")
+        .map(|links| links.fold(0, |total, link| total + text_length(link.as_node())))
+        .unwrap_or(0);
+    link_length as f64 / length as f64
+}
+
+/// Points given by a paragraph to its parent according to its length, 0 for other nodes.
+fn paragraph_score(node: &kuchiki::NodeRef) -> f64 {
+    if !is_any_element(node, &["p", "pre", "td"]) {
+        return 0.;
+    }
+    let text = node.text_contents();
+    let length = text.trim().chars().count();
+    if length < MIN_PARAGRAPH_LENGTH {
+        return 0.;
+    }
+    1. + text.split(',').count() as f64 + ((length / 100) as f64).min(3.)
+}
+
+struct Scorer {
+    unlikely: Regex,
+    maybe: Regex,
+    positive: Regex,
+    negative: Regex,
+}
+
+impl Scorer {
+    fn new() -> Scorer {
+        Scorer {
+            unlikely: Regex::new(UNLIKELY).unwrap(),
+            maybe: Regex::new(MAYBE).unwrap(),
+            positive: Regex::new(POSITIVE).unwrap(),
+            negative: Regex::new(NEGATIVE).unwrap(),
+        }
+    }
+
+    fn is_unlikely(&self, node: &kuchiki::NodeRef) -> bool {
+        if is_any_element(node, &["article", "body", "html", "main"]) {
+            return false;
+        }
+        let names = class_and_id(node);
+        self.unlikely.is_match(&names) && !self.maybe.is_match(&names)
+    }
+
+    fn class_weight(&self, node: &kuchiki::NodeRef) -> f64 {
+        let names = class_and_id(node);
+        let mut weight = 0.;
+        if self.positive.is_match(&names) {
+            weight += 25.;
+        }
+        if self.negative.is_match(&names) {
+            weight -= 25.;
+        }
+        weight
+    }
+
+    fn initial_score(&self, node: &kuchiki::NodeRef) -> f64 {
+        let tag_score = if is_any_element(node, &["article", "main"]) {
+            10.
+        }
+        else if is_element(node, "div") {
+            5.
+        }
+        else if is_any_element(node, &["blockquote", "pre", "td"]) {
+            3.
+        }
+        else if is_any_element(node, &["address", "dd", "dl", "dt", "form", "li", "ol", "ul"]) {
+            -3.
+        }
+        else if is_any_element(node, &["h1", "h2", "h3", "h4", "h5", "h6", "th"]) {
+            -5.
+        }
+        else {
+            0.
+        };
+        tag_score + self.class_weight(node)
+    }
+
+    /// Scores the candidates of the subtree of `node` in a single walk, skipping unlikely
+    /// elements: paragraphs give their points to their parent and half of them to their
+    /// grandparent. Returns the points `node` gives as a paragraph and the points given to it by
+    /// its children.
+    fn score_candidates(&self,
+                        node: &kuchiki::NodeRef,
+                        scores: &mut Vec<(kuchiki::NodeRef, f64)>)
+                        -> (f64, f64) {
+        let mut children_score = 0.;
+        let mut grandchildren_score = 0.;
+        for child in node.children() {
+            if child.as_element().is_none() || self.is_unlikely(&child) {
+                continue;
+            }
+            let (child_score, child_children_score) = self.score_candidates(&child, scores);
+            children_score += child_score;
+            grandchildren_score += child_children_score;
+        }
+        if node.as_element().is_some() && (children_score > 0. || grandchildren_score > 0.) {
+            let score = self.initial_score(node) + children_score + grandchildren_score / 2.;
+            scores.push((node.clone(), score));
+        }
+        (paragraph_score(node), children_score)
+    }
+
+    /// Finds the node holding the main content: paragraphs give points to their parent and
+    /// grandparent according to their length, and candidates are penalized by their link
+    /// density.
+    fn find_content(&self, root: &kuchiki::NodeRef) -> Option<kuchiki::NodeRef> {
+        let mut scores = Vec::new();
+        if !self.is_unlikely(root) {
+            self.score_candidates(root, &mut scores);
+        }
+
+        let mut best: Option<(kuchiki::NodeRef, f64)> = None;
+        for (candidate, score) in scores {
+            let score = score * (1. - link_density(&candidate));
+            let is_better = match best {
+                Some((_, best_score)) => score > best_score,
+                None => true,
+            };
+            if is_better {
+                best = Some((candidate, score));
+            }
+        }
+        best.map(|(candidate, _)| candidate)
+    }
+}
+
+fn auto_extractor() -> Extractor {
+    let mut extractor = Extractor::new(ExtractorOptions {
+        exclude_selectors: vec![EXCLUDED.parse().unwrap()],
+        ..ExtractorOptions::default()
+    });
+    for &(kind, query) in SELECTORS {
+        extractor.add_selector(Selector::new(kind, query.parse().unwrap())
+            .source(query.to_string()));
+    }
+    extractor
+}
+
+/// Extracts the main content of a website without any rule, finding it with heuristics based on
+/// text density, link density and class names. This is a fallback for websites no rule matches:
+/// the result is less accurate than what a rule gives.
+pub fn auto_extract(website: &Website) -> Result<(Document, Report), Error> {
+    let dom = website.dom
+        .as_ref()
+        .ok_or_else(|| Error::NoDom { url: website.request_url.clone() })?;
+
+    let content = Scorer::new()
+        .find_content(dom)
+        .or_else(|| dom.select("body").ok().and_then(|mut bodies| bodies.next()).map(|body| {
+            body.as_node().clone()
+        }))
+        .ok_or_else(|| Error::NoContent { url: website.request_url.clone() })?;
+
+    let url = Url::parse(&website.request_url).ok();
+    let (mut document, mut report) =
+        auto_extractor().extract_content(dom, &content, url.as_ref(), website.reference_time());
+    for warning in &mut report.warnings {
+        warning.error.set_context(None, &website.request_url);
+    }
+    if document.title.is_none() {
+        let title = dom.select("title")
+            .ok()
+            .and_then(|mut titles| titles.next())
+            .map(|title| Part::Text(title.text_contents()).normalized_text());
+        if let Some(title) = title {
+            if !title.is_empty() {
+                document.title = Some(vec![Part::Text(title)]);
+            }
+        }
+    }
+    Ok((document, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use ::part::Part;
+    use ::website::Website;
+    use super::auto_extract;
+
+    #[test]
+    fn test_auto_extract() {
+        let markup = br#"<html><head><title>One year of Rust</title></head><body>
+            <nav><p>Home, blog, about us, contact, and other links to this website</p></nav>
+            <div class="sidebar"><p>Some unrelated text, long enough to be a paragraph.</p></div>
+            <div class="post">
+                <p>Rust is a systems programming language, focused on safety, speed, and
+                concurrency.</p>
+                <p>One year after 1.0, the language and its community keep growing, with
+                <a href="/news">more news</a> every week.</p>
+            </div>
+            <div class="comments"><p>Great post, thanks, I learned a lot, keep it up!</p></div>
+        </body></html>"#;
+        let website = Website::from_bytes("http://example.com/post".to_string(), markup);
+        let (document, _) = auto_extract(&website).unwrap();
+
+        assert_eq!(document.title,
+                   Some(vec![Part::Text("One year of Rust".to_string())]));
+        let paragraphs: Vec<_> = document.content
+            .iter()
+            .filter(|part| match **part {
+                Part::Paragraph(..) => true,
+                _ => false,
+            })
+            .map(|part| part.normalized_text())
+            .collect();
+        assert_eq!(paragraphs,
+                   vec!["Rust is a systems programming language, focused on safety, speed, and \
+                         concurrency.",
+                        "One year after 1.0, the language and its community keep growing, with \
+                         more news every week."]);
+    }
+}
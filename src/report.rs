@@ -0,0 +1,55 @@
+use ::kuchiki;
+use std::fmt;
+use error::Error;
+use extractor::SelectorKind;
+
+/// A recoverable issue encountered while extracting a document. The element is skipped and the
+/// extraction goes on.
+#[derive(Debug)]
+pub struct Warning {
+    /// Kind of the selector that matched the element.
+    pub kind: SelectorKind,
+    /// Source of the selector that matched the element, if known.
+    pub selector: Option<String>,
+    /// Path of the element in the DOM (ex: `html > body > div.post > img#logo`).
+    pub path: String,
+    pub error: Error,
+}
+
+impl Warning {
+    pub fn message(&self) -> String {
+        self.error.to_string()
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.kind)?;
+        if let Some(ref selector) = self.selector {
+            write!(formatter, " `{}`", selector)?;
+        }
+        write!(formatter, " at {}: {}", self.path, self.error)
+    }
+}
+
+/// Issues encountered while extracting a document.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Name of the rule used to extract the document, if any.
+    pub rule: Option<String>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+pub fn dom_path(node: &kuchiki::NodeRef) -> String {
+    let mut elements: Vec<String> = node.inclusive_ancestors()
+        .filter_map(|ancestor| ancestor.as_element().map(Error::describe_element))
+        .collect();
+    elements.reverse();
+    elements.join(" > ")
+}
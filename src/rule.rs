@@ -3,6 +3,7 @@ use ::serde;
 use ::kuchiki;
 use serde::de;
 
+use super::date::{DateFormat, DateLocale, DateOrder, DatePattern};
 use super::extractor::Extractor;
 use super::matcher;
 use super::extractor;
@@ -34,6 +35,12 @@ struct SplatVisitor;
 
 impl de::Visitor for SplatVisitor {
     type Value = Splat;
+    fn visit_str<E>(&mut self, v: &str) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        self.visit_string(v.to_string())
+    }
+
     fn visit_string<E>(&mut self, v: String) -> Result<Self::Value, E>
         where E: de::Error
     {
@@ -53,6 +60,80 @@ impl de::Visitor for SplatVisitor {
     }
 }
 
+/// A string or a list of strings. Decoded as an enum, so that a value of another type reports
+/// the expected string type along with its key.
+#[derive(Debug, Deserialize)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_values(self) -> Vec<String> {
+        match self {
+            StringOrList::String(value) => vec![value],
+            StringOrList::List(values) => values,
+        }
+    }
+}
+
+/// A CSS selector, given either as a string or as a table naming the attribute to read.
+#[derive(Debug)]
+struct SelectorValue {
+    selector: String,
+    attribute: Option<String>,
+}
+
+impl serde::Deserialize for SelectorValue {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde::Deserializer
+    {
+        deserializer.deserialize(SelectorValueVisitor)
+    }
+}
+
+struct SelectorValueVisitor;
+
+impl de::Visitor for SelectorValueVisitor {
+    type Value = SelectorValue;
+    fn visit_str<E>(&mut self, v: &str) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        self.visit_string(v.to_string())
+    }
+
+    fn visit_string<E>(&mut self, v: String) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        Ok(SelectorValue { selector: v, attribute: None })
+    }
+
+    fn visit_map<V>(&mut self, mut visitor: V) -> Result<Self::Value, V::Error>
+        where V: de::MapVisitor
+    {
+        let mut selector = None;
+        let mut attribute = None;
+        while let Some(key) = visitor.visit_key::<String>()? {
+            match &key[..] {
+                "selector" => selector = Some(visitor.visit_value()?),
+                "attribute" => attribute = Some(visitor.visit_value()?),
+                _ => return Err(de::Error::unknown_field(&key)),
+            }
+        }
+        visitor.end()?;
+
+        match selector {
+            Some(selector) => {
+                Ok(SelectorValue {
+                    selector: selector,
+                    attribute: attribute,
+                })
+            }
+            None => visitor.missing_field("selector"),
+        }
+    }
+}
+
 
 impl serde::Deserialize for Rule {
     fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
@@ -77,11 +158,75 @@ impl RuleMapVisitor {
         Ok(())
     }
 
+    fn parse_kuchiki_selectors<E>(&self, value: &str) -> Result<kuchiki::Selectors, E>
+        where E: de::Error
+    {
+        Ok(value.parse().map_err(|_| E::custom("Failed to parse CSS selector"))?)
+    }
+
+    fn visit_exclude<V>(&self, rule: &mut Rule, visitor: &mut V) -> Result<(), V::Error>
+        where V: de::MapVisitor
+    {
+        let splat: Splat = visitor.visit_value()?;
+        for s in splat.values {
+            let selectors = self.parse_kuchiki_selectors(&s)?;
+            rule.extractor.options.exclude_selectors.push(selectors);
+        }
+        Ok(())
+    }
+
+    fn visit_date_format<V>(&self, rule: &mut Rule, visitor: &mut V) -> Result<(), V::Error>
+        where V: de::MapVisitor
+    {
+        let formats: StringOrList = visitor.visit_value()?;
+        for s in formats.into_values() {
+            rule.extractor.options.date_formats.push(match &s[..] {
+                "auto" => DateFormat::Auto,
+                "relative" => DateFormat::Relative,
+                _ => {
+                    DateFormat::Pattern(s.parse::<DatePattern>()
+                        .map_err(|e| de::Error::custom(e.to_string()))?)
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn visit_date_order<V>(&self, rule: &mut Rule, visitor: &mut V) -> Result<(), V::Error>
+        where V: de::MapVisitor
+    {
+        rule.extractor.options.date_order = match &visitor.visit_value::<String>()?[..] {
+            "day-first" => DateOrder::DayFirst,
+            "month-first" => DateOrder::MonthFirst,
+            other => return Err(de::Error::custom(format!("Unknown date order '{}'", other))),
+        };
+        Ok(())
+    }
+
+    fn visit_date_locale<V>(&self, rule: &mut Rule, visitor: &mut V) -> Result<(), V::Error>
+        where V: de::MapVisitor
+    {
+        let value = visitor.visit_value::<String>()?;
+        rule.extractor.options.date_locale = match DateLocale::from_str(&value) {
+            Some(locale) => locale,
+            None => return Err(de::Error::custom(format!("Unknown date locale '{}'", value))),
+        };
+        Ok(())
+    }
+
+    fn visit_date_attributes<V>(&self, rule: &mut Rule, visitor: &mut V) -> Result<(), V::Error>
+        where V: de::MapVisitor
+    {
+        let splat: Splat = visitor.visit_value()?;
+        rule.extractor.options.date_attributes = splat.values;
+        Ok(())
+    }
+
     fn visit_kuchiki_selectors<V>(&self, visitor: &mut V) -> Result<kuchiki::Selectors, V::Error>
         where V: de::MapVisitor
     {
         let value = visitor.visit_value::<String>()?;
-        Ok(value.parse().map_err(|_| de::Error::custom("Failed to parse CSS selector"))?)
+        self.parse_kuchiki_selectors(&value)
     }
 
     fn visit_field<V>(&self,
@@ -93,7 +238,15 @@ impl RuleMapVisitor {
     {
         match name {
             "include_url" => self.visit_include_url(&mut rule, &mut visitor)?,
-            "date_format" => rule.extractor.options.date_format = Some(visitor.visit_value()?),
+            "exclude" => self.visit_exclude(&mut rule, &mut visitor)?,
+            "date_format" => self.visit_date_format(&mut rule, &mut visitor)?,
+            "date_order" => self.visit_date_order(&mut rule, &mut visitor)?,
+            "date_locale" => self.visit_date_locale(&mut rule, &mut visitor)?,
+            "date_attributes" => self.visit_date_attributes(&mut rule, &mut visitor)?,
+            "keep_raw_urls" => rule.extractor.options.keep_raw_urls = visitor.visit_value()?,
+            "metadata_fallback" => {
+                rule.extractor.options.metadata_fallback = visitor.visit_value()?
+            }
             "root" => {
                 rule.extractor.options.root_selector =
                     Some(self.visit_kuchiki_selectors(&mut visitor)?)
@@ -101,9 +254,21 @@ impl RuleMapVisitor {
             selector_kind => {
                 match extractor::SelectorKind::from_str(selector_kind) {
                     Some(kind) => {
-                        let selectors = self.visit_kuchiki_selectors(&mut visitor)?;
-                        rule.extractor
-                            .add_selector(extractor::Selector::new(kind, selectors));
+                        let value: SelectorValue = visitor.visit_value()?;
+                        let selectors = self.parse_kuchiki_selectors(&value.selector)?;
+                        let mut selector = extractor::Selector::new(kind, selectors)
+                            .source(value.selector);
+                        if let Some(attribute) = value.attribute {
+                            // Figures and tables are made of their content only
+                            if kind == extractor::SelectorKind::Figure ||
+                               kind == extractor::SelectorKind::Table {
+                                let message =
+                                    format!("The {} selector can't read an attribute", kind);
+                                return Err(de::Error::custom(message));
+                            }
+                            selector = selector.attribute(attribute);
+                        }
+                        rule.extractor.add_selector(selector);
                     }
                     None => return Err(de::Error::unknown_field(name)),
                 }
@@ -1,11 +1,13 @@
 
 use ::serde;
+use ::url::Url;
 use serde::de;
-use std::error;
 
+use super::error::Error;
 use super::rule::Rule;
 use super::website::Website;
 use super::part::Document;
+use super::report::Report;
 
 #[derive(Debug, Default)]
 pub struct Rules {
@@ -13,15 +15,37 @@ pub struct Rules {
 }
 
 impl Rules {
-    pub fn extract(&self, website: &Website) -> Result<Vec<Document>, Box<error::Error>> {
-        let dom = website.dom.as_ref().ok_or("This website has no DOM")?;
+    pub fn extract(&self, website: &Website) -> Result<Vec<Document>, Error> {
+        Ok(self.extract_with_reports(website)?
+            .into_iter()
+            .map(|(document, _)| document)
+            .collect())
+    }
+
+    /// Same as `extract`, but returns each document along with the issues encountered while
+    /// extracting it.
+    pub fn extract_with_reports(&self,
+                                website: &Website)
+                                -> Result<Vec<(Document, Report)>, Error> {
+        let dom = website.dom
+            .as_ref()
+            .ok_or_else(|| Error::NoDom { url: website.request_url.clone() })?;
 
         let rule = self.rules
             .iter()
             .find(|rule| rule.matchers.iter().any(|m| m.matches(website)))
-            .ok_or("No rule matching this website")?;
-
-        Ok(rule.extractor.extract(dom))
+            .ok_or_else(|| Error::NoMatchingRule { url: website.request_url.clone() })?;
+
+        let url = Url::parse(&website.request_url).ok();
+        let mut documents = rule.extractor
+            .extract(dom, url.as_ref(), website.reference_time());
+        for &mut (_, ref mut report) in &mut documents {
+            report.rule = Some(rule.name.clone());
+            for warning in &mut report.warnings {
+                warning.error.set_context(Some(&rule.name), &website.request_url);
+            }
+        }
+        Ok(documents)
     }
 
     pub fn append(&mut self, mut other: Rules) {
@@ -70,6 +94,9 @@ mod tests {
 
     use std::error::Error;
     use super::Rules;
+    use ::date::{DateFormat, DateLocale, DateOrder};
+    use ::error;
+    use ::website::Website;
 
     #[test]
     fn test_deserialization() {
@@ -87,6 +114,9 @@ mod tests {
         let rules = serde_json::from_str::<Rules>(json).unwrap();
         assert_eq!(rules.rules.len(), 1);
         assert_eq!(rules.rules[0].name, "rustlang_blog");
+        // serde_json hands single strings to the visitor as borrowed `&str`
+        assert_eq!(rules.rules[0].extractor.options.date_formats,
+                   vec![DateFormat::Pattern("%B %d, %Y".parse().unwrap())]);
     }
 
     fn parse_rules_from_str(source: &str) -> Result<Rules, toml::DecodeError> {
@@ -138,6 +168,92 @@ mod tests {
         assert!(error.cause().is_none());
     }
 
+    #[test]
+    fn date_format_can_be_a_string_or_an_array() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        date_format = ["%B %d, %Y", "auto", "relative"]
+        date_order = "day-first"
+        "#)
+            .expect("Failed to parse toml");
+
+        let options = &rules.rules[0].extractor.options;
+        assert_eq!(options.date_formats,
+                   vec![DateFormat::Pattern("%B %d, %Y".parse().unwrap()),
+                        DateFormat::Auto,
+                        DateFormat::Relative]);
+        assert_eq!(options.date_order, DateOrder::DayFirst);
+    }
+
+    #[test]
+    fn fails_if_date_format_is_invalid() {
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           date_format = "%d %(""#);
+
+        assert!(format!("{}", error).starts_with("custom error: Invalid date format '%d %('"));
+    }
+
+    #[test]
+    fn fails_if_date_order_is_unknown() {
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           date_order = "year-first""#);
+
+        assert_eq!(format!("{}", error), "custom error: Unknown date order 'year-first'");
+    }
+
+    #[test]
+    fn parses_date_locale() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        date_locale = "fr"
+        "#)
+            .expect("Failed to parse toml");
+
+        assert_eq!(rules.rules[0].extractor.options.date_locale, DateLocale::French);
+
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           date_locale = "xx""#);
+        assert_eq!(format!("{}", error), "custom error: Unknown date locale 'xx'");
+    }
+
+    #[test]
+    fn selector_can_name_an_attribute() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        include_url = "*//foo.com"
+        title = { selector = "h1", attribute = "data-title" }
+        author = ".author"
+        "#)
+            .expect("Failed to parse toml");
+
+        let website = Website::from_bytes("http://foo.com".to_string(),
+                                          br#"<html><body>
+            <h1 data-title="One year">1 year</h1>
+            <span class="author">Ann</span>
+        </body></html>"#);
+        let documents = rules.extract(&website).unwrap();
+        assert_eq!(documents[0].title,
+                   Some(vec![::part::Part::Text("One year".to_string())]));
+        assert_eq!(documents[0].authors, vec!["Ann".to_string()]);
+    }
+
+    #[test]
+    fn fails_if_selector_table_has_no_selector() {
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           title = { attribute = "content" }"#);
+
+        assert!(format!("{}", error).contains("selector"));
+    }
+
+    #[test]
+    fn fails_if_figure_selector_has_an_attribute() {
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           figure = { selector = "figure", attribute = "id" }"#);
+
+        assert_eq!(format!("{}", error),
+                   "custom error: The figure selector can't read an attribute");
+    }
+
     #[test]
     fn matches_an_url() {
         let rules = parse_rules_from_str(r#"
@@ -164,6 +280,43 @@ mod tests {
         // TODO
     }
 
+    #[test]
+    fn exclude_can_be_a_string_or_an_array() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        exclude = ".ad"
+        [bar]
+        exclude = [".share", ".related"]
+        "#)
+            .expect("Failed to parse toml");
+
+        assert_eq!(rules.rules.len(), 2);
+        let lengths: Vec<_> = rules.rules
+            .iter()
+            .map(|rule| rule.extractor.options.exclude_selectors.len())
+            .collect();
+        assert!(lengths == vec![1, 2] || lengths == vec![2, 1]);
+    }
+
+    #[test]
+    fn metadata_fallback_can_be_disabled() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        metadata_fallback = false
+        "#)
+            .expect("Failed to parse toml");
+
+        assert!(!rules.rules[0].extractor.options.metadata_fallback);
+    }
+
+    #[test]
+    fn fails_if_exclude_has_error() {
+        let error = parse_and_unwrap_error(r#"[foo]
+                                           exclude = ["blih >"]"#);
+
+        assert_eq!(format!("{}", error), "custom error: Failed to parse CSS selector");
+    }
+
     #[test]
     fn fails_if_root_has_error() {
         let error = parse_and_unwrap_error(r#"[foo]
@@ -173,4 +326,38 @@ mod tests {
         assert_eq!(error.description(), "custom error");
         assert!(error.cause().is_none());
     }
+
+    #[test]
+    fn warnings_know_their_rule_and_url() {
+        let rules = parse_rules_from_str(r#"
+        [foo]
+        include_url = "*//foo.com"
+        image = "img"
+        "#)
+            .expect("Failed to parse toml");
+        let website = Website::from_bytes("http://foo.com".to_string(), b"<img id=logo>");
+
+        let (_, report) = rules.extract_with_reports(&website).unwrap().pop().unwrap();
+        let context = report.warnings[0].error.context().unwrap();
+        assert_eq!(context.rule, Some("foo".to_string()));
+        assert_eq!(context.url, Some("http://foo.com".to_string()));
+        assert_eq!(report.warnings[0].error.to_string(),
+                   "The image element img#logo has no src attribute (rule 'foo') in \
+                    http://foo.com");
+    }
+
+    #[test]
+    fn fails_if_no_rule_matches() {
+        let rules = parse_rules_from_str(r#"
+        [wordpress]
+        include_url = "*//foo"
+        "#)
+            .expect("Failed to parse toml");
+        let website = Website::from_bytes("http://bar".to_string(), b"<p>Hi!</p>");
+
+        match rules.extract(&website) {
+            Err(error::Error::NoMatchingRule { ref url }) => assert_eq!(url, "http://bar"),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
 }
@@ -1,32 +1,55 @@
+use ::chrono;
 use ::mime;
 use ::kuchiki;
+use std::fs;
+use std::io;
+use std::path::Path;
+use chrono::TimeZone;
 use hyper::client::Response;
 use hyper::header;
 use kuchiki::traits::TendrilSink;
 use html5ever::driver::BytesOpts;
 use html5ever::encoding::label::encoding_from_whatwg_label;
 
+fn parse_html<R: io::Read>(charset: Option<&str>, input: &mut R) -> io::Result<kuchiki::NodeRef> {
+    let opts = BytesOpts {
+        transport_layer_encoding: charset.and_then(|charset| encoding_from_whatwg_label(charset)),
+    };
+
+    kuchiki::parse_html().from_bytes(opts).read_from(input)
+}
+
+/// Time of the `Date` header of a response.
+fn response_date(response: &Response) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    response.headers.get::<header::Date>().map(|date| {
+        let timestamp = (date.0).0.to_timespec();
+        let naive = chrono::NaiveDateTime::from_timestamp(timestamp.sec, 0);
+        chrono::FixedOffset::east(0).from_utc_datetime(&naive)
+    })
+}
+
 fn parse_dom(mut response: &mut Response) -> Option<kuchiki::NodeRef> {
     match response.headers.get::<header::ContentType>() {
         Some(&header::ContentType(mime::Mime(mime::TopLevel::Text, mime::SubLevel::Html, _))) => {}
         _ => return None,
     }
 
-    let opts = BytesOpts {
-        transport_layer_encoding: response.headers
-            .get::<header::ContentType>()
-            .and_then(|content_type| content_type.get_param(mime::Attr::Charset))
-            .and_then(|charset| encoding_from_whatwg_label(charset)),
-    };
+    let charset = response.headers
+        .get::<header::ContentType>()
+        .and_then(|content_type| content_type.get_param(mime::Attr::Charset))
+        .map(|charset| charset.to_string());
 
-    Some(kuchiki::parse_html().from_bytes(opts).read_from(&mut response).unwrap())
+    Some(parse_html(charset.as_ref().map(String::as_str), &mut response).unwrap())
 }
 
 #[derive(Debug)]
 pub struct Website {
+    /// The URL used to match rules. For websites built from local content, this is the URL the
+    /// content was originally fetched from.
     pub request_url: String,
-    pub response: Response,
+    response: Option<Response>,
     pub dom: Option<kuchiki::NodeRef>,
+    reference_time: Option<chrono::DateTime<chrono::FixedOffset>>,
 }
 
 impl Website {
@@ -34,9 +57,52 @@ impl Website {
         Website {
             request_url: url,
             dom: parse_dom(&mut response),
-            response: response,
+            reference_time: response_date(&response),
+            response: Some(response),
         }
     }
+
+    /// The HTTP response, if the website was downloaded. Websites built from local content have
+    /// none.
+    pub fn response(&self) -> Option<&Response> {
+        self.response.as_ref()
+    }
+
+    /// Builds a website from an HTML document read from `input`. `url` is the effective URL of
+    /// the document, used to match rules.
+    pub fn from_reader<R: io::Read>(url: String, input: &mut R) -> io::Result<Website> {
+        Ok(Website {
+            request_url: url,
+            dom: Some(parse_html(None, input)?),
+            response: None,
+            reference_time: None,
+        })
+    }
+
+    /// Builds a website from raw HTML bytes. `url` is the effective URL of the document, used to
+    /// match rules.
+    pub fn from_bytes(url: String, mut bytes: &[u8]) -> Website {
+        Website::from_reader(url, &mut bytes).expect("Failed to read from a byte slice")
+    }
+
+    /// Builds a website from a local HTML file. `url` is the effective URL of the document, used
+    /// to match rules.
+    pub fn from_file<P: AsRef<Path>>(url: String, path: P) -> io::Result<Website> {
+        Website::from_reader(url, &mut fs::File::open(path)?)
+    }
+
+    /// The time relative dates of the website are measured from: the `Date` header of the
+    /// response, unless set with `set_reference_time`. Websites built from local content have
+    /// none by default, and their relative dates can't be parsed.
+    pub fn reference_time(&self) -> Option<&chrono::DateTime<chrono::FixedOffset>> {
+        self.reference_time.as_ref()
+    }
+
+    /// Sets the time relative dates of the website are measured from, usually the time the
+    /// content was downloaded.
+    pub fn set_reference_time(&mut self, time: chrono::DateTime<chrono::FixedOffset>) {
+        self.reference_time = Some(time);
+    }
 }
 
 #[cfg(test)]
@@ -54,8 +120,44 @@ mod website_from_response {
                                                                  2"));
         assert!(website.dom.is_none());
         assert_eq!(&website.request_url, "http://foo.com");
-        assert_eq!(website.response.headers.get::<header::Server>(),
+        assert_eq!(website.response().unwrap().headers.get::<header::Server>(),
                    Some(&header::Server("mock".to_string())));
     }
 
+    #[test]
+    fn reference_time() {
+        let website = Website::from_response("http://foo.com".to_string(),
+                                             make_mock_response("HTTP/1.1 200 OK\r\n\
+                                                                 Date: Mon, 16 May 2016 \
+                                                                 10:30:00 GMT\r\n\
+                                                                 \r\n\
+                                                                 2"));
+        assert_eq!(website.reference_time().unwrap().to_string(), "2016-05-16 10:30:00 +00:00");
+    }
+
+}
+
+#[cfg(test)]
+mod website_from_bytes {
+    use ::chrono;
+    use chrono::TimeZone;
+    use super::Website;
+
+    #[test]
+    fn result() {
+        let website = Website::from_bytes("http://foo.com".to_string(),
+                                          b"<html><body>Hi!</body></html>");
+        assert!(website.response().is_none());
+        assert_eq!(&website.request_url, "http://foo.com");
+        assert_eq!(website.dom.unwrap().text_contents(), "Hi!");
+    }
+
+    #[test]
+    fn reference_time() {
+        let mut website = Website::from_bytes("http://foo.com".to_string(), b"");
+        assert!(website.reference_time().is_none());
+        let time = chrono::FixedOffset::east(3600).ymd(2016, 5, 16).and_hms(10, 30, 0);
+        website.set_reference_time(time.clone());
+        assert_eq!(website.reference_time(), Some(&time));
+    }
 }